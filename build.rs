@@ -0,0 +1,7 @@
+// The link settings of the Node.js addon (`--features node`), which napi-build knows
+// for every platform.
+
+fn main() {
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
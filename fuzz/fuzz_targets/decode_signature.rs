@@ -0,0 +1,26 @@
+// Both signature decoders accept the same strings and decode them to the same bytes,
+// the buffered one just doesn't allocate for the short ones.
+
+#![no_main]
+
+use craigwrightisnotsatoshi_check::signature::{
+    decode_signature, decode_signature_into, SIGNATURE_LEN,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let signature = match std::str::from_utf8(data) {
+        Ok(signature) => signature,
+        Err(_) => return,
+    };
+
+    let mut buf = [0; SIGNATURE_LEN];
+    match (
+        decode_signature(signature),
+        decode_signature_into(signature, &mut buf),
+    ) {
+        (Ok(a), Ok(b)) => assert_eq!(a, &*b),
+        (Err(_), Err(_)) => {}
+        (a, b) => panic!("decoders disagree on {:?}: {:?} and {:?}", signature, a, b),
+    }
+});
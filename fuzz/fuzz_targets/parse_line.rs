@@ -0,0 +1,17 @@
+// Input lines come from lists found on the internet: no line may make the parsers
+// panic, whatever its bytes.
+
+#![no_main]
+
+use craigwrightisnotsatoshi_check::input::{
+    detect_format, is_comment, parse_json_line, parse_line,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let line = String::from_utf8_lossy(data);
+    let _ = parse_line(&line);
+    let _ = parse_json_line(&line);
+    let _ = is_comment(&line);
+    let _ = detect_format(&line);
+});
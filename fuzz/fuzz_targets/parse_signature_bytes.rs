@@ -0,0 +1,16 @@
+// Arbitrary signature bytes with any header byte are an error, never a panic, and a
+// signature that parses keeps its `r || s`.
+
+#![no_main]
+
+use craigwrightisnotsatoshi_check::signature::{decode_header, parse_signature_bytes};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(header) = data.first() {
+        let _ = decode_header(*header);
+    }
+    if let Ok(signature) = parse_signature_bytes(data) {
+        assert_eq!(signature.serialize()[1..], data[1..]);
+    }
+});
@@ -0,0 +1,33 @@
+#ifndef CWINS_H
+#define CWINS_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define CWINS_VALID 1
+
+#define CWINS_INVALID 0
+
+#define CWINS_ERROR_ARGUMENT -1
+
+#define CWINS_ERROR_ADDRESS -2
+
+#define CWINS_ERROR_ENCODING -3
+
+#define CWINS_ERROR_HEADER -4
+
+#define CWINS_ERROR_SIGNATURE -5
+
+// Checks a base64 signature of `message` (the built-in one if NULL) by a mainnet
+// address. Returns `CWINS_VALID`, `CWINS_INVALID` or a negative `CWINS_ERROR_*`.
+//
+// # Safety
+//
+// Every non-NULL pointer must point to a NUL-terminated string.
+int cwins_verify(const char *address, const char *message, const char *sig_b64);
+
+#endif  /* CWINS_H */
@@ -0,0 +1,99 @@
+// Signed attestations of a run, for sharing audit results: a JSON statement of the
+// input hashes, the results and the tool version, signed as a Bitcoin message by the
+// auditor's key. The statement is kept as the exact string that was signed, so
+// anyone can check it with `--address <address> --signature <signature> --message
+// <statement>`.
+
+use crate::sign::sign_message;
+use bitcoin::address;
+use bitcoin::hashes::{sha256, HashEngine};
+use bitcoin::{AddressType, PrivateKey};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Read};
+use std::sync::{Arc, Mutex};
+
+/// A reader hashing everything read through it into a shared SHA-256 engine.
+pub struct Hashed<R> {
+    inner: R,
+    hash: Arc<Mutex<sha256::HashEngine>>,
+}
+
+impl<R> Hashed<R> {
+    pub fn new(inner: R, hash: Arc<Mutex<sha256::HashEngine>>) -> Self {
+        Hashed { inner, hash }
+    }
+}
+
+impl<R: Read> Read for Hashed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hash.lock().expect("hash lock").input(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for Hashed<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // Already buffered, this doesn't read again.
+        if let Ok(buf) = self.inner.fill_buf() {
+            let amt = amt.min(buf.len());
+            self.hash.lock().expect("hash lock").input(&buf[..amt]);
+        }
+        self.inner.consume(amt);
+    }
+}
+
+/// Signs `statement` with `key` (by its P2PKH address) and returns the bundle with
+/// the signed text, the address and the base64 signature.
+pub fn attest(key: &PrivateKey, statement: &Value) -> Result<Value, address::Error> {
+    let statement = statement.to_string();
+    let (address, signature) = sign_message(key, &statement, AddressType::P2pkh)?;
+
+    Ok(json!({
+        "statement": statement,
+        "address": address.to_string(),
+        "signature": signature,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify_message;
+    use bitcoin::hashes::Hash;
+    use std::io::BufReader;
+
+    #[test]
+    fn check_attestation() {
+        let hash = Arc::new(Mutex::new(sha256::Hash::engine()));
+        let mut reader = Hashed::new(BufReader::new(&b"line one\nline two\n"[..]), hash.clone());
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap() > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        assert_eq!(lines.len(), 2);
+        let digest = sha256::Hash::from_engine(hash.lock().unwrap().clone());
+        assert_eq!(digest, sha256::Hash::hash(b"line one\nline two\n"));
+
+        let key: PrivateKey = "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn"
+            .parse()
+            .unwrap();
+        let bundle = attest(&key, &json!({"inputs": [{"sha256": digest.to_string()}]})).unwrap();
+        let statement = bundle["statement"].as_str().unwrap();
+        assert!(statement.contains(&digest.to_string()));
+        assert_eq!(
+            verify_message(
+                bundle["address"].as_str().unwrap(),
+                statement,
+                bundle["signature"].as_str().unwrap()
+            )
+            .ok(),
+            Some(true)
+        );
+    }
+}
@@ -0,0 +1,98 @@
+// Balances of the verified addresses from an Esplora API (`GET /address/<address>`,
+// e.g. https://blockstream.info/api), to tell how many coins the signers control.
+
+use bitcoin::Amount;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Where balances are looked up, `esplora:<url>` on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BalanceSource {
+    Esplora(String),
+}
+
+impl FromStr for BalanceSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("esplora", url)) if !url.is_empty() => Ok(BalanceSource::Esplora(
+                url.trim_end_matches('/').to_string(),
+            )),
+            _ => Err(format!(
+                "unknown balance source `{}`, expected esplora:<url>",
+                s
+            )),
+        }
+    }
+}
+
+impl BalanceSource {
+    /// The confirmed balance of `address`.
+    pub fn balance(&self, address: &str) -> Result<Amount, String> {
+        let BalanceSource::Esplora(url) = self;
+        let response: Value = ureq::get(&format!("{}/address/{}", url, address))
+            .call()
+            .map_err(|e| e.to_string())?
+            .into_string()
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))?;
+
+        let stat = |name: &str| {
+            response["chain_stats"][name]
+                .as_u64()
+                .ok_or_else(|| format!("no chain_stats.{} in the answer", name))
+        };
+        let funded = stat("funded_txo_sum")?;
+        let spent = stat("spent_txo_sum")?;
+
+        Ok(Amount::from_sat(funded.saturating_sub(spent)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn check_esplora_balance() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let source: BalanceSource =
+            format!("esplora:http://{}/api/", listener.local_addr().unwrap())
+                .parse()
+                .unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+            }
+
+            let body = r#"{"address":"1A","chain_stats":{"funded_txo_sum":150000000,"spent_txo_sum":50000000},"mempool_stats":{}}"#;
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        });
+
+        assert_eq!(source.balance("1A"), Ok(Amount::from_sat(100_000_000)));
+        assert!(server.join().unwrap().starts_with("GET /api/address/1A "));
+
+        assert!("esplora:".parse::<BalanceSource>().is_err());
+        assert!("electrum:host".parse::<BalanceSource>().is_err());
+    }
+}
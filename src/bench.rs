@@ -0,0 +1,180 @@
+// Throughput of the verification paths (`bench`): signatures by synthetic keys are
+// verified one call each like the library API does, one after the other with a
+// `Verifier` and on several threads like `-j` does, so regressions show up as a
+// drop in signatures per second. A list whose first half is malformed compares the
+// work stealing threads of `-j` with fixed chunks of the list per thread.
+
+use crate::cache::{steal_work, VerificationCache};
+use crate::sign::sign_message;
+use crate::{check_sig, Verifier};
+use bitcoin::address::NetworkChecked;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::SecretKey;
+use bitcoin::{Address, AddressType, Network, PrivateKey};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// `count` P2PKH addresses and their signatures of `message`. The keys are derived
+/// from their index, every run checks the same signatures.
+pub fn signatures(count: usize, message: &str) -> Vec<(Address<NetworkChecked>, String)> {
+    (0..count as u64)
+        .map(|i| {
+            let seed = sha256::Hash::hash(&i.to_le_bytes());
+            let key = SecretKey::from_slice(&seed[..]).expect("a hash is a valid key");
+            let key = PrivateKey::new(key, Network::Bitcoin);
+            sign_message(&key, message, AddressType::P2pkh).expect("P2PKH can be signed for")
+        })
+        .collect()
+}
+
+/// Timings of one benchmark run.
+#[derive(Debug, Clone, Copy)]
+pub struct BenchReport {
+    pub signatures: usize,
+    /// Signatures found valid on the serial path, all of them unless it is broken.
+    pub valid: usize,
+    /// One [`check_sig`] call per signature, hashing the message every time.
+    pub per_call: Duration,
+    pub serial: Duration,
+    pub jobs: usize,
+    pub parallel: Duration,
+    /// The mixed list on `jobs` threads, one fixed chunk each.
+    pub mixed_chunked: Duration,
+    /// The mixed list on `jobs` threads taking the next line when done with one.
+    pub mixed_stealing: Duration,
+}
+
+impl BenchReport {
+    pub fn per_second(&self, elapsed: Duration) -> f64 {
+        let seconds = elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.signatures as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The `signatures` with the first half replaced by malformed ones, rejected before
+/// any key recovery: cheap lines followed by expensive ones.
+pub fn mixed(
+    signatures: &[(Address<NetworkChecked>, String)],
+) -> Vec<(Address<NetworkChecked>, String)> {
+    let malformed = signatures.len() / 2;
+    signatures
+        .iter()
+        .enumerate()
+        .map(|(i, (address, signature))| match i < malformed {
+            true => (address.clone(), "not a signature".to_string()),
+            false => (address.clone(), signature.clone()),
+        })
+        .collect()
+}
+
+/// Verifies `signatures` on `jobs` threads with a fixed share of the list each, the
+/// way `-j` split the work before [`steal_work`].
+fn chunked(signatures: &[(Address<NetworkChecked>, String)], message: &str, jobs: usize) {
+    let chunk_size = signatures.len().div_ceil(jobs.max(1)).max(1);
+    thread::scope(|scope| {
+        for chunk in signatures.chunks(chunk_size) {
+            scope.spawn(move || {
+                let verifier = Verifier::new(message);
+                for (address, signature) in chunk {
+                    let _ = verifier.verify_one(address, signature);
+                }
+            });
+        }
+    });
+}
+
+fn stealing(signatures: &[(Address<NetworkChecked>, String)], message: &str, jobs: usize) {
+    steal_work(
+        signatures,
+        jobs,
+        |verifier: &mut Option<Verifier>, (address, signature)| {
+            let _ = verifier
+                .get_or_insert_with(|| Verifier::new(message))
+                .verify_one(address, signature);
+        },
+    );
+}
+
+/// Verifies the `signatures` of `message` one call each, serially with one verifier
+/// and then on `jobs` threads, and their [`mixed`] list on `jobs` threads both ways.
+pub fn run(
+    signatures: &[(Address<NetworkChecked>, String)],
+    message: &str,
+    jobs: usize,
+) -> BenchReport {
+    let started = Instant::now();
+    for (address, signature) in signatures {
+        let _ = check_sig(address.clone(), message, signature);
+    }
+    let per_call = started.elapsed();
+
+    let pairs: Vec<(Address<NetworkChecked>, &str)> = signatures
+        .iter()
+        .map(|(address, signature)| (address.clone(), signature.as_str()))
+        .collect();
+    let started = Instant::now();
+    let results = Verifier::new(message).verify_batch(&pairs);
+    let serial = started.elapsed();
+
+    let entries: Vec<_> = signatures
+        .iter()
+        .map(|(address, signature)| (address.clone(), signature.clone(), message.to_string()))
+        .collect();
+    let started = Instant::now();
+    VerificationCache::default().prefetch(&entries, jobs);
+    let parallel = started.elapsed();
+
+    let mixed = mixed(signatures);
+    let started = Instant::now();
+    chunked(&mixed, message, jobs);
+    let mixed_chunked = started.elapsed();
+    let started = Instant::now();
+    stealing(&mixed, message, jobs);
+    let mixed_stealing = started.elapsed();
+
+    BenchReport {
+        signatures: signatures.len(),
+        valid: results.iter().filter(|r| matches!(r, Ok(true))).count(),
+        per_call,
+        serial,
+        jobs,
+        parallel,
+        mixed_chunked,
+        mixed_stealing,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MESSAGE;
+
+    #[test]
+    fn check_bench() {
+        let signatures = signatures(8, MESSAGE);
+        assert_eq!(signatures, super::signatures(8, MESSAGE));
+        assert_ne!(signatures[0].0, signatures[1].0);
+
+        let report = run(&signatures, MESSAGE, 2);
+        assert_eq!(report.signatures, 8);
+        assert_eq!(report.valid, 8);
+        assert_eq!(report.jobs, 2);
+        assert!(report.per_second(report.serial) > 0.0);
+        assert!(report.per_second(report.per_call) > 0.0);
+        assert_eq!(report.per_second(Duration::ZERO), 0.0);
+
+        let mixed = mixed(&signatures);
+        assert_eq!(
+            mixed[..4]
+                .iter()
+                .filter(|(_, s)| s == "not a signature")
+                .count(),
+            4
+        );
+        assert_eq!(mixed[4..], signatures[4..]);
+    }
+}
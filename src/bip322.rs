@@ -0,0 +1,378 @@
+// BIP-322 generic signed message verification [0].
+//
+// A BIP-322 signature proves that the owner of an address could spend a virtual
+// `to_spend` output committing to the message. The proof is either a "simple" one
+// (only the consensus encoded witness stack of the spending input) or a "full" one
+// (the whole consensus encoded `to_sign` transaction).
+//
+// Only single-key scripts are checked here: P2WPKH, P2SH-P2WPKH and P2TR key path
+// spends, plus P2PKH for full proofs. Anything needing a script interpreter is
+// reported as unsupported.
+//
+// Refs.:
+// - [0] https://github.com/bitcoin/bips/blob/master/bip-0322.mediawiki
+
+use crate::signature::secp;
+use crate::VerifyError;
+use bitcoin::address::NetworkChecked;
+use bitcoin::blockdata::opcodes::all::OP_RETURN;
+use bitcoin::blockdata::opcodes::OP_0;
+use bitcoin::blockdata::script::{Builder, Instruction, PushBytesBuf};
+use bitcoin::consensus::encode::deserialize;
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use bitcoin::secp256k1::{Message, XOnlyPublicKey};
+use bitcoin::sighash::{Prevouts, SighashCache};
+use bitcoin::{
+    absolute, ecdsa, taproot, Address, OutPoint, PublicKey, Script, ScriptBuf, Sequence,
+    Transaction, TxIn, TxOut, Txid, Witness,
+};
+
+const TAG: &[u8] = b"BIP0322-signed-message";
+
+/// The decoded signature: either just the witness stack or the whole `to_sign` tx.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Proof {
+    Simple(Witness),
+    Full(Transaction),
+}
+
+/// Tagged hash `SHA256(SHA256(tag) || SHA256(tag) || message)`.
+pub fn message_hash(message: impl AsRef<[u8]>) -> sha256::Hash {
+    let tag = sha256::Hash::hash(TAG);
+
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_byte_array());
+    engine.input(tag.as_byte_array());
+    engine.input(message.as_ref());
+    sha256::Hash::from_engine(engine)
+}
+
+/// The virtual transaction whose only output is "spent" by the proof.
+pub fn to_spend(script_pubkey: &Script, message: impl AsRef<[u8]>) -> Transaction {
+    Transaction {
+        version: 0,
+        lock_time: absolute::LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint {
+                txid: Txid::all_zeros(),
+                vout: 0xFFFFFFFF,
+            },
+            script_sig: Builder::new()
+                .push_opcode(OP_0)
+                .push_slice(message_hash(message).to_byte_array())
+                .into_script(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: 0,
+            script_pubkey: script_pubkey.to_owned(),
+        }],
+    }
+}
+
+/// The transaction spending `to_spend` that a simple proof's witness belongs to.
+pub fn to_sign(to_spend: &Transaction, witness: Witness) -> Transaction {
+    Transaction {
+        version: 0,
+        lock_time: absolute::LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint {
+                txid: to_spend.txid(),
+                vout: 0,
+            },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness,
+        }],
+        output: vec![TxOut {
+            value: 0,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    }
+}
+
+pub fn decode_proof(bytes: &[u8]) -> Result<Proof, VerifyError> {
+    if let Ok(witness) = deserialize::<Witness>(bytes) {
+        return Ok(Proof::Simple(witness));
+    }
+    if let Ok(tx) = deserialize::<Transaction>(bytes) {
+        return Ok(Proof::Full(tx));
+    }
+
+    Err(VerifyError::InvalidBip322Proof(
+        "neither a witness stack nor a transaction",
+    ))
+}
+
+/// Verifies a decoded (base64) BIP-322 proof of `message` by `address`.
+pub fn verify(
+    address: &Address<NetworkChecked>,
+    message: impl AsRef<[u8]>,
+    proof: &Proof,
+) -> Result<bool, VerifyError> {
+    let to_spend = to_spend(&address.script_pubkey(), message);
+
+    let to_sign = match proof {
+        Proof::Simple(witness) => to_sign(&to_spend, witness.clone()),
+        Proof::Full(tx) => {
+            let expected_input = OutPoint {
+                txid: to_spend.txid(),
+                vout: 0,
+            };
+            if tx.input.len() != 1 || tx.input[0].previous_output != expected_input {
+                return Err(VerifyError::InvalidBip322Proof(
+                    "to_sign must only spend the to_spend output",
+                ));
+            }
+            if tx.output.len() != 1
+                || tx.output[0].value != 0
+                || !tx.output[0].script_pubkey.is_op_return()
+            {
+                return Err(VerifyError::InvalidBip322Proof(
+                    "to_sign must have a single empty OP_RETURN output",
+                ));
+            }
+            tx.clone()
+        }
+    };
+
+    verify_spend(&to_spend.output[0], &to_sign)
+}
+
+fn verify_spend(prevout: &TxOut, to_sign: &Transaction) -> Result<bool, VerifyError> {
+    let spk = &prevout.script_pubkey;
+    let input = &to_sign.input[0];
+
+    if spk.is_v0_p2wpkh() {
+        if !input.script_sig.is_empty() {
+            return Err(VerifyError::InvalidBip322Proof(
+                "native segwit spends have an empty scriptSig",
+            ));
+        }
+        verify_p2wpkh(spk, to_sign)
+    } else if spk.is_p2sh() {
+        let redeem_script = match single_push(&input.script_sig) {
+            Some(script) if script.is_v0_p2wpkh() => script,
+            _ => {
+                return Err(VerifyError::InvalidBip322Proof(
+                    "only P2SH-P2WPKH is supported for P2SH addresses",
+                ))
+            }
+        };
+        if ScriptBuf::new_p2sh(&redeem_script.script_hash()) != *spk {
+            return Ok(false);
+        }
+        verify_p2wpkh(&redeem_script, to_sign)
+    } else if spk.is_v1_p2tr() {
+        verify_p2tr(prevout, to_sign)
+    } else if spk.is_p2pkh() {
+        verify_p2pkh(spk, to_sign)
+    } else {
+        Err(VerifyError::InvalidBip322Proof("unsupported address type"))
+    }
+}
+
+fn single_push(script: &Script) -> Option<ScriptBuf> {
+    let mut instructions = script.instructions();
+
+    match (instructions.next(), instructions.next()) {
+        (Some(Ok(Instruction::PushBytes(bytes))), None) => {
+            Some(ScriptBuf::from_bytes(bytes.as_bytes().to_vec()))
+        }
+        _ => None,
+    }
+}
+
+fn verify_ecdsa(sighash: &[u8], signature: &ecdsa::Signature, pubkey: &PublicKey) -> bool {
+    let secp = secp();
+
+    match Message::from_slice(sighash) {
+        Ok(msg) => secp
+            .verify_ecdsa(&msg, &signature.sig, &pubkey.inner)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn parse_ecdsa(bytes: &[u8]) -> Result<ecdsa::Signature, VerifyError> {
+    ecdsa::Signature::from_slice(bytes)
+        .map_err(|_| VerifyError::InvalidBip322Proof("malformed ECDSA signature"))
+}
+
+fn parse_pubkey(bytes: &[u8]) -> Result<PublicKey, VerifyError> {
+    PublicKey::from_slice(bytes)
+        .map_err(|_| VerifyError::InvalidBip322Proof("malformed public key"))
+}
+
+/// `witness_program` is the P2WPKH script, either the address itself or the redeem
+/// script of a P2SH-P2WPKH address.
+fn verify_p2wpkh(witness_program: &Script, to_sign: &Transaction) -> Result<bool, VerifyError> {
+    let witness = &to_sign.input[0].witness;
+    if witness.len() != 2 {
+        return Err(VerifyError::InvalidBip322Proof(
+            "P2WPKH witness must be a signature and a public key",
+        ));
+    }
+
+    let signature = parse_ecdsa(&witness[0])?;
+    let pubkey = parse_pubkey(&witness[1])?;
+
+    match pubkey.wpubkey_hash() {
+        Some(hash) if ScriptBuf::new_v0_p2wpkh(&hash) == *witness_program => {}
+        _ => return Ok(false),
+    }
+
+    let sighash = SighashCache::new(to_sign)
+        .segwit_signature_hash(
+            0,
+            &ScriptBuf::new_p2pkh(&pubkey.pubkey_hash()),
+            0,
+            signature.hash_ty,
+        )
+        .map_err(|_| VerifyError::InvalidBip322Proof("cannot compute the sighash"))?;
+
+    Ok(verify_ecdsa(&sighash[..], &signature, &pubkey))
+}
+
+fn verify_p2tr(prevout: &TxOut, to_sign: &Transaction) -> Result<bool, VerifyError> {
+    let witness = &to_sign.input[0].witness;
+    if witness.len() != 1 {
+        return Err(VerifyError::InvalidBip322Proof(
+            "only taproot key path spends are supported",
+        ));
+    }
+
+    let signature = taproot::Signature::from_slice(&witness[0])
+        .map_err(|_| VerifyError::InvalidBip322Proof("malformed Schnorr signature"))?;
+    let output_key = XOnlyPublicKey::from_slice(&prevout.script_pubkey.as_bytes()[2..])
+        .map_err(|_| VerifyError::InvalidBip322Proof("invalid taproot output key"))?;
+
+    let sighash = SighashCache::new(to_sign)
+        .taproot_key_spend_signature_hash(
+            0,
+            &Prevouts::All(std::slice::from_ref(prevout)),
+            signature.hash_ty,
+        )
+        .map_err(|_| VerifyError::InvalidBip322Proof("cannot compute the sighash"))?;
+
+    let secp = secp();
+    let msg = Message::from_slice(&sighash[..])
+        .map_err(|_| VerifyError::InvalidBip322Proof("cannot compute the sighash"))?;
+
+    Ok(secp
+        .verify_schnorr(&signature.sig, &msg, &output_key)
+        .is_ok())
+}
+
+fn verify_p2pkh(spk: &Script, to_sign: &Transaction) -> Result<bool, VerifyError> {
+    let input = &to_sign.input[0];
+    if !input.witness.is_empty() {
+        return Err(VerifyError::InvalidBip322Proof(
+            "P2PKH spends have an empty witness",
+        ));
+    }
+
+    let pushes: Vec<PushBytesBuf> = input
+        .script_sig
+        .instructions()
+        .map(|i| match i {
+            Ok(Instruction::PushBytes(bytes)) => Ok(bytes.to_owned()),
+            _ => Err(VerifyError::InvalidBip322Proof(
+                "P2PKH scriptSig must only push data",
+            )),
+        })
+        .collect::<Result<_, _>>()?;
+    if pushes.len() != 2 {
+        return Err(VerifyError::InvalidBip322Proof(
+            "P2PKH scriptSig must be a signature and a public key",
+        ));
+    }
+
+    let signature = parse_ecdsa(pushes[0].as_bytes())?;
+    let pubkey = parse_pubkey(pushes[1].as_bytes())?;
+    if ScriptBuf::new_p2pkh(&pubkey.pubkey_hash()) != *spk {
+        return Ok(false);
+    }
+
+    let sighash = SighashCache::new(to_sign)
+        .legacy_signature_hash(0, spk, signature.hash_ty.to_u32())
+        .map_err(|_| VerifyError::InvalidBip322Proof("cannot compute the sighash"))?;
+
+    Ok(verify_ecdsa(&sighash[..], &signature, &pubkey))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use bitcoin::address::NetworkUnchecked;
+    use bitcoin::Network;
+
+    fn address(s: &str) -> Address<NetworkChecked> {
+        s.parse::<Address<NetworkUnchecked>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap()
+    }
+
+    fn proof(s: &str) -> Proof {
+        decode_proof(&base64::engine::general_purpose::STANDARD.decode(s).unwrap()).unwrap()
+    }
+
+    // Test vectors from BIP-322.
+    #[test]
+    fn check_message_hash() {
+        assert_eq!(
+            message_hash("").to_string(),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+        assert_eq!(
+            message_hash("Hello World").to_string(),
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+        );
+    }
+
+    #[test]
+    fn check_simple_p2wpkh() {
+        let address = address("bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l");
+
+        let empty = proof("AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=");
+        let hello = proof("AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=");
+
+        assert_eq!(verify(&address, "", &empty).ok(), Some(true));
+        assert_eq!(verify(&address, "Hello World", &hello).ok(), Some(true));
+        assert_eq!(verify(&address, "Hello World", &empty).ok(), Some(false));
+    }
+
+    #[test]
+    fn check_simple_p2tr() {
+        let address = address("bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3");
+        let hello = proof("AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==");
+
+        assert_eq!(verify(&address, "Hello World", &hello).ok(), Some(true));
+        assert_eq!(verify(&address, "Hello World!", &hello).ok(), Some(false));
+    }
+
+    #[test]
+    fn check_full_proof_structure() {
+        let address = address("bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l");
+        let witness = match proof("AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=") {
+            Proof::Simple(w) => w,
+            Proof::Full(_) => unreachable!(),
+        };
+
+        let mut tx = to_sign(&to_spend(&address.script_pubkey(), "Hello World"), witness);
+        let full = Proof::Full(tx.clone());
+        assert_eq!(verify(&address, "Hello World", &full).ok(), Some(true));
+
+        let encoded = bitcoin::consensus::encode::serialize(&tx);
+        assert_eq!(decode_proof(&encoded).unwrap(), full);
+
+        tx.output[0].value = 1;
+        assert!(matches!(
+            verify(&address, "Hello World", &Proof::Full(tx)),
+            Err(VerifyError::InvalidBip322Proof(_))
+        ));
+    }
+}
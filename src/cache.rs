@@ -0,0 +1,370 @@
+use crate::signature::signed_msg_hash;
+use crate::{Verifier, VerifyError};
+use bitcoin::address::NetworkChecked;
+use bitcoin::hashes::sha256d;
+use bitcoin::Address;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use tracing::{info, warn};
+
+/// The first line of a cache file, followed by the stamp of its results.
+const HEADER: &str = "# cwins-check cache ";
+
+/// Address, signature and signed-message hash.
+type Key = (String, String, sha256d::Hash);
+
+/// Verification results keyed by address, signature and message hash, persisted
+/// between runs with `--cache`.
+#[derive(Debug, Default)]
+pub struct VerificationCache {
+    entries: BTreeMap<Key, bool>,
+    /// The tool version and settings the results were made with, see [`Self::load`].
+    stamp: String,
+    /// The verifier of the last message checked.
+    verifier: Option<Verifier>,
+}
+
+impl PartialEq for VerificationCache {
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+/// The verifier in `slot`, replaced first if it is for another message.
+fn verifier_for<'a>(slot: &'a mut Option<Verifier>, message: &[u8]) -> &'a Verifier {
+    if slot.as_ref().map(Verifier::message) != Some(message) {
+        *slot = Some(Verifier::new(message));
+    }
+    slot.as_ref().expect("verifier just set")
+}
+
+/// The verified entries of a [`VerificationCache::prefetch_unordered`] as the threads
+/// get done with them.
+pub struct Prefetching {
+    /// The index of an entry and its result, if it is one to remember.
+    done: mpsc::Receiver<(usize, Option<(Key, bool)>)>,
+}
+
+/// Maps `items` with `f` on `jobs` threads, in no particular order. Each thread takes
+/// the next item as it gets done with one, so a run of expensive items doesn't leave
+/// one thread with all of them; `f` gets the thread's own `S`, e.g. its verifier.
+pub fn steal_work<T: Sync, S: Default, R: Send>(
+    items: &[T],
+    jobs: usize,
+    f: impl Fn(&mut S, &T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.clamp(1, items.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut state = S::default();
+                    let mut results = Vec::new();
+                    while let Some(item) = items.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        results.push(f(&mut state, item));
+                    }
+                    results
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().expect("verification thread panicked"))
+            .collect()
+    })
+}
+
+impl VerificationCache {
+    /// One `<message hash> <address> <signature> <0|1>` entry per line.
+    pub fn parse(content: &str) -> Option<Self> {
+        let mut entries = BTreeMap::new();
+
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split(' ').collect();
+            if fields.len() != 4 {
+                return None;
+            }
+
+            let msg_hash = fields[0].parse::<sha256d::Hash>().ok()?;
+            let valid = match fields[3] {
+                "1" => true,
+                "0" => false,
+                _ => return None,
+            };
+            entries.insert(
+                (fields[1].to_string(), fields[2].to_string(), msg_hash),
+                valid,
+            );
+        }
+
+        Some(VerificationCache {
+            entries,
+            stamp: String::new(),
+            verifier: None,
+        })
+    }
+
+    /// An empty cache for results made with `stamp`.
+    pub fn new(stamp: &str) -> Self {
+        VerificationCache {
+            stamp: stamp.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Loads the results saved with the same `stamp` (the tool version and the
+    /// settings a verdict may depend on); a file saved with another one is stale and
+    /// starts over empty.
+    pub fn load(path: &Path, stamp: &str) -> Self {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::new(stamp),
+            Err(e) => {
+                warn!("Ignoring unreadable cache file {}: {}", path.display(), e);
+                return Self::new(stamp);
+            }
+        };
+
+        let header = format!("{}{}\n", HEADER, stamp);
+        let Some(content) = content.strip_prefix(&header) else {
+            info!(
+                "Discarding cache file {} made with other settings",
+                path.display()
+            );
+            return Self::new(stamp);
+        };
+        match Self::parse(content) {
+            Some(cache) => VerificationCache {
+                stamp: stamp.to_string(),
+                ..cache
+            },
+            None => {
+                warn!("Ignoring corrupt cache file {}", path.display());
+                Self::new(stamp)
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut content = format!("{}{}\n", HEADER, self.stamp);
+
+        for ((address, signature, msg_hash), valid) in &self.entries {
+            content.push_str(&format!(
+                "{} {} {} {}\n",
+                msg_hash, address, signature, *valid as u8
+            ));
+        }
+
+        fs::write(path, content)
+    }
+
+    /// Forgets every result.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the cached result or verifies and remembers it. Errors aren't cached.
+    pub fn check(
+        &mut self,
+        address: &Address<NetworkChecked>,
+        message: impl AsRef<[u8]>,
+        signature: &str,
+    ) -> Result<bool, VerifyError> {
+        // The message hash comes with the verifier, it isn't hashed again every line.
+        let verifier = verifier_for(&mut self.verifier, message.as_ref());
+        let key = (
+            address.to_string(),
+            signature.to_string(),
+            verifier.msg_hash(),
+        );
+
+        if let Some(valid) = self.entries.get(&key) {
+            return Ok(*valid);
+        }
+
+        let valid = verifier.verify_one(address, signature)?;
+        self.entries.insert(key, valid);
+        Ok(valid)
+    }
+
+    /// Verifies the uncached `(address, signature, message)` entries on `jobs` threads,
+    /// so the following `check` calls are just lookups. Errors are left for `check`
+    /// to report.
+    pub fn prefetch<M: AsRef<[u8]> + Sync>(
+        &mut self,
+        entries: &[(Address<NetworkChecked>, String, M)],
+        jobs: usize,
+    ) {
+        let pending: Vec<(Key, &Address<NetworkChecked>, &[u8])> = entries
+            .iter()
+            .map(|(address, signature, message)| {
+                let key = (
+                    address.to_string(),
+                    signature.clone(),
+                    signed_msg_hash(message),
+                );
+                (key, address, message.as_ref())
+            })
+            .filter(|(key, _, _)| !self.entries.contains_key(key))
+            .collect();
+        if pending.is_empty() {
+            return;
+        }
+
+        let results = steal_work(&pending, jobs, |verifier, (key, address, message)| {
+            let verifier = verifier_for(verifier, message);
+            (key.clone(), verifier.verify_one(address, &key.1))
+        });
+        for (key, result) in results {
+            if let Ok(valid) = result {
+                self.entries.insert(key, valid);
+            }
+        }
+    }
+
+    /// Verifies the uncached entries on `jobs` threads in the background; see
+    /// [`Self::next_prefetched`] for the entries as they get done.
+    pub fn prefetch_unordered(
+        &self,
+        entries: Vec<(Address<NetworkChecked>, String, Vec<u8>)>,
+        jobs: usize,
+    ) -> Prefetching {
+        let (sender, done) = mpsc::channel();
+        let mut pending = Vec::new();
+        for (index, (address, signature, message)) in entries.into_iter().enumerate() {
+            let key = (address.to_string(), signature, signed_msg_hash(&message));
+            if self.entries.contains_key(&key) {
+                let _ = sender.send((index, None));
+            } else {
+                pending.push((index, key, address, message));
+            }
+        }
+
+        thread::spawn(move || {
+            steal_work(
+                &pending,
+                jobs,
+                |verifier, (index, key, address, message)| {
+                    let valid = verifier_for(verifier, message).verify_one(address, &key.1);
+                    // Nobody is waiting any more after the run stopped.
+                    let _ = sender.send((*index, valid.ok().map(|valid| (key.clone(), valid))));
+                },
+            );
+        });
+        Prefetching { done }
+    }
+
+    /// Waits for the next entry of `prefetching` done, remembers its result and
+    /// returns its index, `None` once all of them are.
+    pub fn next_prefetched(&mut self, prefetching: &Prefetching) -> Option<usize> {
+        let (index, result) = prefetching.done.recv().ok()?;
+        if let Some((key, valid)) = result {
+            self.entries.insert(key, valid);
+        }
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MESSAGE;
+    use bitcoin::Network;
+
+    #[test]
+    fn check_verification_cache() {
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+
+        let mut cache = VerificationCache::default();
+        assert_eq!(cache.check(&address, MESSAGE, signature).ok(), Some(true));
+        assert_eq!(cache.check(&address, "other", signature).ok(), Some(false));
+        assert!(cache.check(&address, MESSAGE, "bad").is_err());
+        assert_eq!(cache.entries.len(), 2);
+
+        let path = std::env::temp_dir().join("cwins-check-cache-test.txt");
+        cache.stamp = "1.0".to_string();
+        cache.save(&path).unwrap();
+        let loaded = VerificationCache::load(&path, "1.0");
+        assert_eq!(loaded, cache);
+        // Another version drops the results.
+        let stale = VerificationCache::load(&path, "1.1");
+        assert!(stale.entries.is_empty());
+
+        // A changed message is a different key, a stale result is never reused.
+        let key = (
+            address.to_string(),
+            signature.to_string(),
+            signed_msg_hash(MESSAGE),
+        );
+        assert_eq!(loaded.entries.get(&key), Some(&true));
+
+        fs::write(&path, "# cwins-check cache 1.0\ngarbage\n").unwrap();
+        let corrupt = VerificationCache::load(&path, "1.0");
+        assert_eq!(corrupt, VerificationCache::default());
+        fs::remove_file(&path).unwrap();
+        let missing = VerificationCache::load(&path, "1.0");
+        assert_eq!(missing, VerificationCache::default());
+    }
+
+    #[test]
+    fn check_prefetch() {
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let entries = vec![
+            (address.clone(), "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=".to_string(), MESSAGE.to_string()),
+            (address.clone(), "HFjd/SzCNDyXRY/skSjEKusK/adVtBf0ldT1ayvPb+WsLa5Qr0A4seEXjOmtg9K/wcJnv/E3F5TezZNB/ULoZI8=".to_string(), MESSAGE.to_string()),
+            (address.clone(), "bad".to_string(), MESSAGE.to_string()),
+        ];
+
+        let mut cache = VerificationCache::default();
+        cache.prefetch(&entries, 4);
+        assert_eq!(cache.entries.len(), 2);
+
+        let mut sequential = VerificationCache::default();
+        for (address, signature, message) in &entries {
+            let _ = sequential.check(address, message, signature);
+        }
+        assert_eq!(cache, sequential);
+
+        // In the background every entry comes back once, the cached first one too.
+        let mut unordered = VerificationCache::default();
+        let _ = unordered.check(&entries[0].0, MESSAGE, &entries[0].1);
+        let entries = entries
+            .into_iter()
+            .map(|(address, signature, message)| (address, signature, message.into_bytes()))
+            .collect();
+        let prefetching = unordered.prefetch_unordered(entries, 4);
+        let mut done = Vec::new();
+        while let Some(index) = unordered.next_prefetched(&prefetching) {
+            done.push(index);
+        }
+        done.sort();
+        assert_eq!(done, [0, 1, 2]);
+        assert_eq!(unordered, sequential);
+    }
+
+    #[test]
+    fn check_steal_work() {
+        let items: Vec<u64> = (0..100).collect();
+        let mut results = steal_work(&items, 4, |_: &mut (), i| i * 2);
+        results.sort();
+        assert_eq!(results, items.iter().map(|i| i * 2).collect::<Vec<_>>());
+
+        assert_eq!(steal_work(&items[..1], 0, |_: &mut (), i| *i), [0]);
+        assert!(steal_work(&[] as &[u64], 4, |_: &mut (), i| *i).is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+// Signed messages of the UTXO altcoins that kept Bitcoin's scheme and only changed
+// the magic prefix and the address encodings.
+
+use crate::signature::{parse_signature, secp};
+use crate::VerifyError;
+use bitcoin::address::{Error as AddressError, Payload, WitnessProgram, WitnessVersion};
+use bitcoin::consensus::Encodable;
+use bitcoin::hashes::{sha256d, Hash, HashEngine};
+use bitcoin::{base58, bech32, PubkeyHash, PublicKey, ScriptHash, VarInt};
+use std::str::FromStr;
+
+/// A chain whose signed messages are checked with the Bitcoin machinery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Chain {
+    #[default]
+    Bitcoin,
+    Litecoin,
+    Dogecoin,
+    /// Bitcoin Cash, legacy or CashAddr (`bitcoincash:q...`) addresses.
+    BitcoinCash,
+}
+
+impl Chain {
+    /// The text hashed in front of every message.
+    pub fn magic(self) -> &'static str {
+        match self {
+            Chain::Bitcoin | Chain::BitcoinCash => "Bitcoin Signed Message:\n",
+            Chain::Litecoin => "Litecoin Signed Message:\n",
+            Chain::Dogecoin => "Dogecoin Signed Message:\n",
+        }
+    }
+
+    /// Base58 version bytes of P2PKH and P2SH addresses.
+    fn versions(self) -> (u8, u8) {
+        match self {
+            Chain::Bitcoin | Chain::BitcoinCash => (0x00, 0x05),
+            Chain::Litecoin => (0x30, 0x32),
+            Chain::Dogecoin => (0x1e, 0x16),
+        }
+    }
+
+    /// Human readable part of segwit addresses, if the chain has them.
+    fn hrp(self) -> Option<&'static str> {
+        match self {
+            Chain::Bitcoin => Some("bc"),
+            Chain::Litecoin => Some("ltc"),
+            Chain::Dogecoin | Chain::BitcoinCash => None,
+        }
+    }
+
+    /// Double SHA-256 of the length-prefixed magic and message.
+    pub fn msg_hash(self, message: impl AsRef<[u8]>) -> sha256d::Hash {
+        let mut engine = sha256d::Hash::engine();
+        for part in [self.magic().as_bytes(), message.as_ref()] {
+            VarInt(part.len() as u64)
+                .consensus_encode(&mut engine)
+                .expect("engines don't error");
+            engine.input(part);
+        }
+
+        sha256d::Hash::from_engine(engine)
+    }
+
+    /// The script an address of this chain pays to.
+    pub fn parse_address(self, address: &str) -> Result<Payload, AddressError> {
+        if self == Chain::BitcoinCash {
+            if let Some(payload) = cashaddr_decode(address) {
+                return Ok(payload);
+            }
+        }
+
+        if let Some(hrp) = self.hrp() {
+            if address.to_lowercase().starts_with(&format!("{}1", hrp)) {
+                let (_, data, _) = bech32::decode(address)?;
+                let (version, program) =
+                    data.split_first().ok_or(AddressError::EmptyBech32Payload)?;
+                let program: Vec<u8> = bech32::FromBase32::from_base32(program)?;
+                let version = WitnessVersion::try_from(*version)?;
+                return Ok(Payload::WitnessProgram(WitnessProgram::new(
+                    version, program,
+                )?));
+            }
+        }
+
+        let data = base58::decode_check(address)?;
+        let (p2pkh, p2sh) = self.versions();
+        match data.split_first() {
+            Some((version, hash)) if *version == p2pkh && hash.len() == 20 => Ok(
+                Payload::PubkeyHash(PubkeyHash::from_slice(hash).expect("20 bytes")),
+            ),
+            Some((version, hash)) if *version == p2sh && hash.len() == 20 => Ok(
+                Payload::ScriptHash(ScriptHash::from_slice(hash).expect("20 bytes")),
+            ),
+            _ => Err(AddressError::UnknownAddressType(address.to_string())),
+        }
+    }
+}
+
+impl FromStr for Chain {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bitcoin" | "btc" => Ok(Chain::Bitcoin),
+            "litecoin" | "ltc" => Ok(Chain::Litecoin),
+            "dogecoin" | "doge" => Ok(Chain::Dogecoin),
+            "bitcoin-cash" | "bch" => Ok(Chain::BitcoinCash),
+            _ => Err(format!(
+                "unknown chain `{}`, expected bitcoin, litecoin, dogecoin or bitcoin-cash",
+                s
+            )),
+        }
+    }
+}
+
+const CASHADDR_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn cashaddr_polymod(values: impl Iterator<Item = u8>) -> u64 {
+    let mut c: u64 = 1;
+    for d in values {
+        let c0 = c >> 35;
+        c = ((c & 0x07ffffffff) << 5) ^ d as u64;
+        for (bit, generator) in [
+            0x98f2bc8e61,
+            0x79b76d99e2,
+            0xf33e5fb3c4,
+            0xae2eabe2a8,
+            0x1e4f43e470,
+        ]
+        .iter()
+        .enumerate()
+        {
+            if c0 & (1 << bit) != 0 {
+                c ^= generator;
+            }
+        }
+    }
+
+    c ^ 1
+}
+
+/// The P2PKH or P2SH payload of a `bitcoincash:` CashAddr, the prefix may be left out.
+fn cashaddr_decode(address: &str) -> Option<Payload> {
+    let address = address.to_lowercase();
+    let data = address.strip_prefix("bitcoincash:").unwrap_or(&address);
+
+    let values = data
+        .bytes()
+        .map(|c| {
+            CASHADDR_CHARSET
+                .iter()
+                .position(|&x| x == c)
+                .map(|p| p as u8)
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    if values.len() < 8 {
+        return None;
+    }
+
+    let checked = b"bitcoincash"
+        .iter()
+        .map(|c| c & 0x1f)
+        .chain([0])
+        .chain(values.iter().copied());
+    if cashaddr_polymod(checked) != 0 {
+        return None;
+    }
+
+    let data = values[..values.len() - 8]
+        .iter()
+        .map(|&v| bech32::u5::try_from_u8(v).ok())
+        .collect::<Option<Vec<_>>>()?;
+    let payload: Vec<u8> = bech32::FromBase32::from_base32(&data).ok()?;
+    let (version, hash) = payload.split_first()?;
+    if hash.len() != 20 {
+        return None;
+    }
+    match version >> 3 {
+        0 => Some(Payload::PubkeyHash(PubkeyHash::from_slice(hash).ok()?)),
+        1 => Some(Payload::ScriptHash(ScriptHash::from_slice(hash).ok()?)),
+        _ => None,
+    }
+}
+
+/// Verifies a base64 BIP-137 style `signature` of `message` by an address of `chain`.
+pub fn check_sig_on_chain(
+    chain: Chain,
+    address: &str,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    let payload = chain.parse_address(address)?;
+
+    let secp = secp();
+    let sss = parse_signature(signature)?;
+    let pubkey: PublicKey = sss.recover_pubkey(secp, chain.msg_hash(message))?;
+
+    let matches = match &payload {
+        Payload::PubkeyHash(_) => Payload::p2pkh(&pubkey) == payload,
+        Payload::ScriptHash(_) => Payload::p2shwpkh(&pubkey).is_ok_and(|p| p == payload),
+        Payload::WitnessProgram(_) => Payload::p2wpkh(&pubkey).is_ok_and(|p| p == payload),
+        _ => false,
+    };
+
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use bitcoin::secp256k1::{Message, Secp256k1, SecretKey};
+    use bitcoin::sign_message::signed_msg_hash;
+
+    fn sign(chain: Chain, key: &SecretKey, header: u8, message: &str) -> String {
+        let secp = Secp256k1::new();
+        let msg = Message::from_slice(chain.msg_hash(message).as_byte_array()).unwrap();
+        let (id, sig) = secp.sign_ecdsa_recoverable(&msg, key).serialize_compact();
+        let mut bytes = vec![header + id.to_i32() as u8];
+        bytes.extend_from_slice(&sig);
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    fn base58(version: u8, hash: &[u8]) -> String {
+        base58::encode_check(&[&[version], hash].concat())
+    }
+
+    #[test]
+    fn check_altcoin_signatures() {
+        assert_eq!(Chain::Bitcoin.msg_hash("hi"), signed_msg_hash("hi"));
+        assert_ne!(Chain::Litecoin.msg_hash("hi"), signed_msg_hash("hi"));
+
+        assert_eq!(
+            Chain::BitcoinCash
+                .parse_address("bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6a")
+                .unwrap(),
+            Chain::Bitcoin
+                .parse_address("1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu")
+                .unwrap()
+        );
+        assert!(Chain::BitcoinCash
+            .parse_address("bitcoincash:qpm2qsznhks23z7629mms6s4cwef74vcwvy22gdx6b")
+            .is_err());
+
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&[3u8; 32]).unwrap();
+        let pubkey = PublicKey::new(key.public_key(&secp));
+        let hash = pubkey.pubkey_hash();
+
+        let ltc = base58(0x30, hash.as_byte_array());
+        let signature = sign(Chain::Litecoin, &key, 31, "hi");
+        assert!(check_sig_on_chain(Chain::Litecoin, &ltc, "hi", &signature).unwrap());
+        assert!(!check_sig_on_chain(Chain::Litecoin, &ltc, "ho", &signature).unwrap());
+        assert!(check_sig_on_chain(Chain::Bitcoin, &ltc, "hi", &signature).is_err());
+
+        let program = bech32::ToBase32::to_base32(&pubkey.wpubkey_hash().unwrap().to_byte_array());
+        let data = [vec![bech32::u5::try_from_u8(0).unwrap()], program].concat();
+        let ltc_segwit = bech32::encode("ltc", data, bech32::Variant::Bech32).unwrap();
+        let signature = sign(Chain::Litecoin, &key, 39, "hi");
+        assert!(check_sig_on_chain(Chain::Litecoin, &ltc_segwit, "hi", &signature).unwrap());
+
+        let doge = base58(0x1e, hash.as_byte_array());
+        let signature = sign(Chain::Dogecoin, &key, 31, "hi");
+        assert!(check_sig_on_chain(Chain::Dogecoin, &doge, "hi", &signature).unwrap());
+        assert!(!check_sig_on_chain(Chain::Litecoin, &ltc, "hi", &signature).unwrap());
+
+        assert_eq!("bch".parse(), Ok(Chain::BitcoinCash));
+        assert!("monero".parse::<Chain>().is_err());
+    }
+}
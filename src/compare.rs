@@ -0,0 +1,170 @@
+// Differences between two runs (`diff old.json new.json`): addresses added to or
+// removed from the list, verdicts that changed and signatures that were replaced. The
+// runs are read from the `--output-format json` or `ndjson` output.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The line of one address in a run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub signature: String,
+    /// `valid`, `invalid` or `error`.
+    pub result: String,
+}
+
+/// The entries of a run by address, the first line of an address listed twice.
+pub type Run = BTreeMap<String, Entry>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(String, Entry),
+    Removed(String, Entry),
+    /// Same signature, another verdict (e.g. after a fix of the verifier).
+    Verdict(String, Entry, Entry),
+    /// Another signature for the address, with the old and new verdicts.
+    Signature(String, Entry, Entry),
+}
+
+impl fmt::Display for Change {
+    /// `ADDED`, `REMOVED`, `CHANGED` or `SIGNATURE <address> - ...` lines.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added(address, new) => write!(f, "ADDED {} - {}", address, new.result),
+            Change::Removed(address, old) => write!(f, "REMOVED {} - {}", address, old.result),
+            Change::Verdict(address, old, new) => {
+                write!(f, "CHANGED {} - {} -> {}", address, old.result, new.result)
+            }
+            Change::Signature(address, old, new) => write!(
+                f,
+                "SIGNATURE {} - {} -> {}: {}",
+                address, old.result, new.result, new.signature
+            ),
+        }
+    }
+}
+
+fn entry(row: &Value) -> Option<(String, Entry)> {
+    let field = |name: &str| row.get(name).and_then(Value::as_str).map(str::to_string);
+    Some((
+        field("address")?,
+        Entry {
+            signature: field("signature")?,
+            result: field("result")?,
+        },
+    ))
+}
+
+/// The result rows of a JSON report or NDJSON lines.
+pub fn parse_rows(text: &str) -> Result<Vec<Value>, String> {
+    let rows = match serde_json::from_str::<Value>(text) {
+        // A single NDJSON line is a JSON document too.
+        Ok(row) if row.get("address").is_some() => vec![row],
+        Ok(report) => report
+            .get("results")
+            .and_then(Value::as_array)
+            .cloned()
+            .ok_or("no `results` array in the JSON report")?,
+        Err(_) => text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(n, line)| {
+                serde_json::from_str(line).map_err(|e| format!("line {}: {}", n + 1, e))
+            })
+            .collect::<Result<_, _>>()?,
+    };
+
+    Ok(rows)
+}
+
+/// Reads a run from a JSON report or NDJSON lines.
+pub fn parse_run(text: &str) -> Result<Run, String> {
+    let rows = parse_rows(text)?;
+    let mut run = Run::new();
+    for (n, row) in rows.iter().enumerate() {
+        let (address, entry) = entry(row).ok_or(format!("result {} isn't a result row", n + 1))?;
+        run.entry(address).or_insert(entry);
+    }
+
+    Ok(run)
+}
+
+/// The changes from `old` to `new`, by address.
+pub fn compare(old: &Run, new: &Run) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for (address, old_entry) in old {
+        match new.get(address) {
+            None => changes.push(Change::Removed(address.clone(), old_entry.clone())),
+            Some(new_entry) if new_entry.signature != old_entry.signature => changes.push(
+                Change::Signature(address.clone(), old_entry.clone(), new_entry.clone()),
+            ),
+            Some(new_entry) if new_entry.result != old_entry.result => changes.push(
+                Change::Verdict(address.clone(), old_entry.clone(), new_entry.clone()),
+            ),
+            Some(_) => {}
+        }
+    }
+    for (address, new_entry) in new {
+        if !old.contains_key(address) {
+            changes.push(Change::Added(address.clone(), new_entry.clone()));
+        }
+    }
+
+    changes.sort_by(|a, b| address(a).cmp(address(b)));
+    changes
+}
+
+fn address(change: &Change) -> &str {
+    match change {
+        Change::Added(address, _)
+        | Change::Removed(address, _)
+        | Change::Verdict(address, _, _)
+        | Change::Signature(address, _, _) => address,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_compare() {
+        let old = parse_run(
+            r#"{"schema_version":1,"results":[
+                {"address":"1A","signature":"s1","result":"valid","error":null,"pubkey":null},
+                {"address":"1B","signature":"s2","result":"invalid","error":null,"pubkey":null},
+                {"address":"1C","signature":"s3","result":"valid","error":null,"pubkey":null},
+                {"address":"1D","signature":"s4","result":"valid","error":null,"pubkey":null}
+            ]}"#,
+        )
+        .unwrap();
+        let new = parse_run(concat!(
+            r#"{"address":"1A","signature":"s1","result":"valid","schema_version":1}"#,
+            "\n",
+            r#"{"address":"1B","signature":"s2","result":"valid","schema_version":1}"#,
+            "\n\n",
+            r#"{"address":"1C","signature":"s5","result":"invalid","schema_version":1}"#,
+            "\n",
+            r#"{"address":"1E","signature":"s6","result":"error","schema_version":1}"#,
+            "\n",
+        ))
+        .unwrap();
+
+        let changes: Vec<String> = compare(&old, &new).iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            [
+                "CHANGED 1B - invalid -> valid",
+                "SIGNATURE 1C - valid -> invalid: s5",
+                "REMOVED 1D - valid",
+                "ADDED 1E - error",
+            ]
+        );
+        assert!(compare(&old, &old).is_empty());
+
+        assert!(parse_run(r#"{"schema_version":1}"#).is_err());
+        assert!(parse_run("{\"address\":\"1A\"}\n").is_err());
+    }
+}
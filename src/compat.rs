@@ -0,0 +1,121 @@
+use crate::signature::{decode_signature_into, SIGNATURE_LEN};
+use bitcoin::address::NetworkChecked;
+use bitcoin::{Address, AddressType};
+use std::fmt;
+use std::str::FromStr;
+
+/// Which BIP-137 header bytes are accepted for an address type.
+///
+/// Wallets disagree on the header of segwit signatures: Electrum signs them with the
+/// P2PKH one (31-34), Trezor uses the BIP-137 offsets (35-38 for P2SH-P2WPKH, 39-42 for
+/// P2WPKH). The key behind the address is verified either way, this only decides
+/// whether a signature with an unexpected header still counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compat {
+    /// Any header, as long as the recovered key matches the address.
+    #[default]
+    Auto,
+    /// Only the header BIP-137 prescribes for the address type.
+    Strict,
+    /// P2PKH headers for every address type.
+    Electrum,
+    /// BIP-137 segwit headers, P2SH-P2WPKH and P2WPKH ones not told apart.
+    Trezor,
+}
+
+impl Compat {
+    /// Whether `header` is accepted for a signature by an address of `address_type`.
+    pub fn allows_header(self, header: u8, address_type: Option<AddressType>) -> bool {
+        let p2pkh = (27..=34).contains(&header);
+        let p2shwpkh = (35..=38).contains(&header);
+        let p2wpkh = (39..=42).contains(&header);
+
+        match (self, address_type) {
+            (Compat::Auto, _) => true,
+            (_, Some(AddressType::P2pkh)) => p2pkh,
+            (Compat::Electrum, Some(AddressType::P2sh | AddressType::P2wpkh)) => {
+                (31..=34).contains(&header)
+            }
+            (Compat::Strict, Some(AddressType::P2sh)) => p2shwpkh,
+            (Compat::Strict, Some(AddressType::P2wpkh)) => p2wpkh,
+            (Compat::Trezor, Some(AddressType::P2sh | AddressType::P2wpkh)) => p2shwpkh || p2wpkh,
+            _ => true,
+        }
+    }
+
+    /// Whether the header of a base64 `signature` is accepted for `address`. Only
+    /// 65 byte BIP-137 signatures have one, anything else is always accepted.
+    pub fn allows(self, address: &Address<NetworkChecked>, signature: &str) -> bool {
+        let mut buf = [0; SIGNATURE_LEN];
+        match decode_signature_into(signature, &mut buf) {
+            Ok(bytes) if bytes.len() == SIGNATURE_LEN => {
+                self.allows_header(bytes[0], address.address_type())
+            }
+            _ => true,
+        }
+    }
+}
+
+impl FromStr for Compat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Compat::Auto),
+            "strict" => Ok(Compat::Strict),
+            "electrum" => Ok(Compat::Electrum),
+            "trezor" => Ok(Compat::Trezor),
+            _ => Err(format!(
+                "unknown mode `{}`, expected auto, strict, electrum or trezor",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Compat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Compat::Auto => "auto",
+            Compat::Strict => "strict",
+            Compat::Electrum => "electrum",
+            Compat::Trezor => "trezor",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_compat_modes() {
+        let p2pkh = Some(AddressType::P2pkh);
+        let p2sh = Some(AddressType::P2sh);
+        let p2wpkh = Some(AddressType::P2wpkh);
+
+        for mode in [
+            Compat::Auto,
+            Compat::Strict,
+            Compat::Electrum,
+            Compat::Trezor,
+        ] {
+            assert!(mode.allows_header(27, p2pkh));
+            assert!(mode.allows_header(31, p2pkh));
+            assert_eq!(mode.allows_header(39, p2pkh), mode == Compat::Auto);
+        }
+
+        assert!(Compat::Auto.allows_header(31, p2wpkh));
+        assert!(Compat::Electrum.allows_header(31, p2wpkh));
+        assert!(!Compat::Electrum.allows_header(39, p2wpkh));
+        assert!(!Compat::Electrum.allows_header(27, p2sh));
+        assert!(Compat::Strict.allows_header(35, p2sh));
+        assert!(!Compat::Strict.allows_header(39, p2sh));
+        assert!(!Compat::Strict.allows_header(31, p2wpkh));
+        assert!(Compat::Trezor.allows_header(39, p2sh));
+        assert!(!Compat::Trezor.allows_header(31, p2sh));
+
+        assert_eq!("trezor".parse(), Ok(Compat::Trezor));
+        assert!("bitcoin-core".parse::<Compat>().is_err());
+    }
+}
@@ -0,0 +1,81 @@
+// Defaults for the command line from `~/.config/cwins-check/config.toml` or
+// `--config PATH`:
+//
+//     message = "We are all Satoshi"
+//     network = "testnet"
+//     output-format = "ndjson"
+//     jobs = 8
+//     compat = "strict"
+//
+// Flags given on the command line take precedence over the file.
+
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub message: Option<String>,
+    pub network: Option<String>,
+    pub output_format: Option<String>,
+    pub jobs: Option<usize>,
+    pub compat: Option<String>,
+}
+
+impl Config {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    /// The file at `path`, an empty config if `required` is false and it doesn't exist.
+    pub fn load(path: &Path, required: bool) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/cwins-check/config.toml`, by default under `~/.config`.
+pub fn default_path() -> Option<PathBuf> {
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("cwins-check").join("config.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_config() {
+        let config = Config::parse(
+            "message = \"hi\\nthere\"\nnetwork = \"testnet\"\noutput-format = \"json\"\njobs = 4\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                message: Some("hi\nthere".to_string()),
+                network: Some("testnet".to_string()),
+                output_format: Some("json".to_string()),
+                jobs: Some(4),
+                compat: None,
+            }
+        );
+
+        assert_eq!(Config::parse(""), Ok(Config::default()));
+        assert!(Config::parse("job = 4").is_err());
+        assert!(Config::parse("jobs = \"many\"").is_err());
+
+        let missing = env::temp_dir().join("cwins-check-no-such-config.toml");
+        assert_eq!(Config::load(&missing, false), Ok(Config::default()));
+        assert!(Config::load(&missing, true).is_err());
+    }
+}
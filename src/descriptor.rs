@@ -0,0 +1,288 @@
+use crate::signature::secp;
+use bitcoin::bip32::{ChildNumber, DerivationPath, ExtendedPubKey};
+use bitcoin::secp256k1::XOnlyPublicKey;
+use bitcoin::{Address, Network, PublicKey};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const INPUT_CHARSET: &str =
+    "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn polymod(c: u64, val: u64) -> u64 {
+    let c0 = c >> 35;
+    let mut c = ((c & 0x7ffffffff) << 5) ^ val;
+    for (bit, generator) in [
+        0xf5dee51989,
+        0xa9fdca3312,
+        0x1bab10e32d,
+        0x3706b1677a,
+        0x644d626ffd,
+    ]
+    .iter()
+    .enumerate()
+    {
+        if c0 & (1 << bit) != 0 {
+            c ^= generator;
+        }
+    }
+    c
+}
+
+/// The 8 character BIP-380 checksum of a descriptor (without the `#`).
+pub fn checksum(descriptor: &str) -> Option<String> {
+    let mut c = 1;
+    let mut cls = 0;
+    let mut cls_count = 0;
+    for ch in descriptor.chars() {
+        let pos = INPUT_CHARSET.find(ch)? as u64;
+        c = polymod(c, pos & 31);
+        cls = cls * 3 + (pos >> 5);
+        cls_count += 1;
+        if cls_count == 3 {
+            c = polymod(c, cls);
+            cls = 0;
+            cls_count = 0;
+        }
+    }
+    if cls_count > 0 {
+        c = polymod(c, cls);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+
+    Some(
+        (0..8)
+            .map(|j| CHECKSUM_CHARSET[((c >> (5 * (7 - j))) & 31) as usize] as char)
+            .collect(),
+    )
+}
+
+/// Output types of the single-key descriptors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    Pkh,
+    Wpkh,
+    ShWpkh,
+    Tr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Key {
+    Single(PublicKey),
+    XOnly(XOnlyPublicKey),
+    Extended {
+        xpub: ExtendedPubKey,
+        path: DerivationPath,
+        wildcard: bool,
+    },
+}
+
+/// A single-key output descriptor: `pkh(KEY)`, `wpkh(KEY)`, `sh(wpkh(KEY))` or `tr(KEY)`
+/// where `KEY` is a hex public key or an xpub with a non-hardened path, optionally
+/// ending in `/*`. Key origins (`[fingerprint/path]`) are accepted and ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Descriptor {
+    pub kind: ScriptKind,
+    key: Key,
+}
+
+fn parse_key(key: &str) -> Result<Key, String> {
+    let key = match key.strip_prefix('[') {
+        Some(rest) => rest.split_once(']').ok_or("unclosed key origin")?.1,
+        None => key,
+    };
+
+    let mut parts = key.split('/');
+    let first = parts.next().unwrap_or_default();
+    if let Ok(xpub) = ExtendedPubKey::from_str(first) {
+        let mut path = Vec::new();
+        let mut wildcard = false;
+        for part in parts {
+            if wildcard {
+                return Err("the wildcard must be the last step".to_string());
+            }
+            match part {
+                "*" => wildcard = true,
+                "*'" | "*h" => return Err("hardened wildcards need the private key".to_string()),
+                part => path.push(ChildNumber::from_str(part).map_err(|e| e.to_string())?),
+            }
+        }
+
+        return Ok(Key::Extended {
+            xpub,
+            path: DerivationPath::from(path),
+            wildcard,
+        });
+    }
+    if parts.next().is_some() {
+        return Err(format!("`{}` is not an extended public key", first));
+    }
+
+    if key.len() == 64 {
+        return XOnlyPublicKey::from_str(key)
+            .map(Key::XOnly)
+            .map_err(|e| e.to_string());
+    }
+    PublicKey::from_str(key)
+        .map(Key::Single)
+        .map_err(|e| e.to_string())
+}
+
+impl FromStr for Descriptor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = match s.split_once('#') {
+            Some((descriptor, sum)) => {
+                if checksum(descriptor).as_deref() != Some(sum) {
+                    return Err("invalid descriptor checksum".to_string());
+                }
+                descriptor
+            }
+            None => s,
+        };
+
+        let inner = |prefix: &str| s.strip_prefix(prefix).and_then(|r| r.strip_suffix(')'));
+        let (kind, key) = if let Some(key) = inner("sh(wpkh(") {
+            (
+                ScriptKind::ShWpkh,
+                key.strip_suffix(')').ok_or("unbalanced parentheses")?,
+            )
+        } else if let Some(key) = inner("pkh(") {
+            (ScriptKind::Pkh, key)
+        } else if let Some(key) = inner("wpkh(") {
+            (ScriptKind::Wpkh, key)
+        } else if let Some(key) = inner("tr(") {
+            (ScriptKind::Tr, key)
+        } else {
+            return Err(
+                "only pkh(), wpkh(), sh(wpkh()) and tr() descriptors are supported".to_string(),
+            );
+        };
+
+        let key = parse_key(key)?;
+        match (kind, &key) {
+            (ScriptKind::Tr, Key::Single(k)) if !k.compressed => {
+                return Err("tr() needs a compressed or x-only key".to_string())
+            }
+            (ScriptKind::Pkh | ScriptKind::Wpkh | ScriptKind::ShWpkh, Key::XOnly(_)) => {
+                return Err("x-only keys are only allowed in tr()".to_string())
+            }
+            _ => {}
+        }
+
+        Ok(Descriptor { kind, key })
+    }
+}
+
+impl Descriptor {
+    /// Whether the descriptor ends in a `/*` wildcard and so has many addresses.
+    pub fn is_ranged(&self) -> bool {
+        matches!(self.key, Key::Extended { wildcard: true, .. })
+    }
+
+    /// The address at `index` of the wildcard, a non-ranged descriptor ignores it.
+    pub fn address(&self, index: u32, network: Network) -> Result<Address, String> {
+        let secp = secp();
+
+        let key = match &self.key {
+            Key::Single(key) => *key,
+            Key::XOnly(key) => {
+                return Ok(Address::p2tr(secp, *key, None, network));
+            }
+            Key::Extended {
+                xpub,
+                path,
+                wildcard,
+            } => {
+                let mut path = path.clone();
+                if *wildcard {
+                    path =
+                        path.child(ChildNumber::from_normal_idx(index).map_err(|e| e.to_string())?);
+                }
+                PublicKey::new(
+                    xpub.derive_pub(secp, &path)
+                        .map_err(|e| e.to_string())?
+                        .public_key,
+                )
+            }
+        };
+
+        match self.kind {
+            ScriptKind::Pkh => Ok(Address::p2pkh(&key, network)),
+            ScriptKind::Wpkh => Address::p2wpkh(&key, network).map_err(|e| e.to_string()),
+            ScriptKind::ShWpkh => Address::p2shwpkh(&key, network).map_err(|e| e.to_string()),
+            ScriptKind::Tr => Ok(Address::p2tr(
+                secp,
+                key.inner.x_only_public_key().0,
+                None,
+                network,
+            )),
+        }
+    }
+
+    /// The addresses of the indexes `0..range` (just one for a non-ranged descriptor)
+    /// with their index.
+    pub fn addresses(&self, range: u32, network: Network) -> Result<HashMap<Address, u32>, String> {
+        let range = if self.is_ranged() { range } else { 1 };
+
+        (0..range)
+            .map(|i| self.address(i, network).map(|a| (a, i)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::bip32::ExtendedPrivKey;
+    use bitcoin::secp256k1::Secp256k1;
+
+    #[test]
+    fn check_descriptors() {
+        assert_eq!(checksum("raw(deadbeef)").as_deref(), Some("89f8spxm"));
+
+        let secp = Secp256k1::new();
+        let xpriv = ExtendedPrivKey::new_master(Network::Bitcoin, &[7u8; 32]).unwrap();
+        let xpub = ExtendedPubKey::from_priv(&secp, &xpriv);
+        let child = |path: &str| {
+            let path: DerivationPath = path.parse().unwrap();
+            PublicKey::new(xpub.derive_pub(&secp, &path).unwrap().public_key)
+        };
+
+        let text = format!("wpkh([d34db33f/84h/0h/0h]{}/0/*)", xpub);
+        let descriptor: Descriptor = text.parse().unwrap();
+        assert!(descriptor.is_ranged());
+        assert_eq!(
+            descriptor.address(5, Network::Bitcoin).unwrap(),
+            Address::p2wpkh(&child("m/0/5"), Network::Bitcoin).unwrap()
+        );
+        let addresses = descriptor.addresses(10, Network::Bitcoin).unwrap();
+        assert_eq!(addresses.len(), 10);
+
+        let with_checksum = format!("{}#{}", text, checksum(&text).unwrap());
+        assert_eq!(with_checksum.parse::<Descriptor>(), Ok(descriptor));
+        assert!(format!("{}#qqqqqqqq", text).parse::<Descriptor>().is_err());
+
+        let key = child("m/1");
+        let descriptor: Descriptor = format!("sh(wpkh({}))", key).parse().unwrap();
+        assert!(!descriptor.is_ranged());
+        assert_eq!(
+            descriptor.addresses(10, Network::Bitcoin).unwrap(),
+            HashMap::from([(Address::p2shwpkh(&key, Network::Bitcoin).unwrap(), 0)])
+        );
+        let descriptor: Descriptor = format!("pkh({}/2)", xpub).parse().unwrap();
+        assert_eq!(
+            descriptor.address(0, Network::Bitcoin).unwrap(),
+            Address::p2pkh(&child("m/2"), Network::Bitcoin)
+        );
+
+        assert!(format!("wpkh({}/0/*h)", xpub)
+            .parse::<Descriptor>()
+            .is_err());
+        assert!(format!("wsh(pk({}))", key).parse::<Descriptor>().is_err());
+    }
+}
@@ -0,0 +1,250 @@
+// Cross-checking the signers against the coinbase outputs of the first blocks
+// (`--early-blocks-file`): keys that mined before 2011 make the statement weigh more
+// than fresh ones. The list has `<address> [<note>]` lines, e.g. the date of the
+// first block paying the address, as `early-blocks` makes them from the `blk*.dat`
+// files of a node.
+
+use crate::input::is_comment;
+use bitcoin::consensus::deserialize;
+use bitcoin::{Address, Block, Network, PublicKey, Script};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 2011-01-01 00:00 UTC, the default end of the early blocks.
+pub const BEFORE_2011: u32 = 1293840000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EarlyError {
+    #[error("cannot read {0}: {1}")]
+    Io(String, #[source] io::Error),
+    #[error("{0} has no block at offset {1}")]
+    Block(String, usize),
+}
+
+/// The listed addresses with their notes.
+#[derive(Debug, Default)]
+pub struct EarlyAddresses {
+    notes: HashMap<String, String>,
+}
+
+impl EarlyAddresses {
+    /// One address per line, the rest of the line is its note; blank lines and `#`
+    /// comments are ignored.
+    pub fn parse(list: &str) -> Self {
+        let notes = list
+            .lines()
+            .filter(|line| !is_comment(line))
+            .map(|line| {
+                let line = line.trim();
+                let (address, note) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+                (address.to_string(), note.trim().to_string())
+            })
+            .collect();
+
+        EarlyAddresses { notes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// The listed address of a signer and its note: `address` itself or, as the
+    /// early coinbases paid bare keys, the P2PKH address of `pubkey` (the key that
+    /// signed) with either compression.
+    pub fn find(&self, address: &str, pubkey: Option<&PublicKey>) -> Option<(String, &str)> {
+        if let Some(note) = self.notes.get(address) {
+            return Some((address.to_string(), note));
+        }
+
+        let pubkey = pubkey?;
+        [pubkey.compressed, !pubkey.compressed]
+            .into_iter()
+            .map(|compressed| {
+                let key = PublicKey {
+                    compressed,
+                    inner: pubkey.inner,
+                };
+                Address::p2pkh(&key, Network::Bitcoin).to_string()
+            })
+            .find_map(|p2pkh| {
+                let note = self.notes.get(&p2pkh)?;
+                Some((p2pkh, note.as_str()))
+            })
+    }
+}
+
+/// The address paid by a coinbase output: P2PKH for a bare (P2PK) key.
+fn output_address(script: &Script) -> Option<Address> {
+    let bytes = script.as_bytes();
+    let key = match bytes.len() {
+        67 if bytes[0] == 65 && bytes[66] == 0xac => &bytes[1..66],
+        35 if bytes[0] == 33 && bytes[34] == 0xac => &bytes[1..34],
+        _ => return Address::from_script(script, Network::Bitcoin).ok(),
+    };
+
+    PublicKey::from_slice(key)
+        .ok()
+        .map(|key| Address::p2pkh(&key, Network::Bitcoin))
+}
+
+/// Adds the addresses of the coinbase outputs of the blocks made before `before` (a
+/// Unix time) in the `blk*.dat` file `path` to `found`, with the time of the earliest
+/// block paying each. Files obfuscated with the `xor.dat` key next to them are
+/// decoded. Returns the number of early blocks.
+pub fn scan_blk_file(
+    path: &Path,
+    before: u32,
+    found: &mut BTreeMap<String, u32>,
+) -> Result<usize, EarlyError> {
+    let name = path.display().to_string();
+    let mut data = fs::read(path).map_err(|e| EarlyError::Io(name.clone(), e))?;
+    let xor = path.with_file_name("xor.dat");
+    if xor.exists() {
+        let key = fs::read(&xor).map_err(|e| EarlyError::Io(xor.display().to_string(), e))?;
+        if key.iter().any(|b| *b != 0) {
+            for (byte, k) in data.iter_mut().zip(key.iter().cycle()) {
+                *byte ^= k;
+            }
+        }
+    }
+
+    let magic = Network::Bitcoin.magic().to_bytes();
+    let mut blocks = 0;
+    let mut offset = 0;
+    // The end of a file is preallocated, zeros after the last block.
+    while offset + 8 <= data.len() && data[offset..offset + 4] != [0; 4] {
+        let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().expect("4 bytes"));
+        let end = offset + 8 + size as usize;
+        let block: Block = match data.get(offset + 8..end) {
+            Some(bytes) if data[offset..offset + 4] == magic => {
+                deserialize(bytes).map_err(|_| EarlyError::Block(name.clone(), offset))?
+            }
+            _ => return Err(EarlyError::Block(name, offset)),
+        };
+        offset = end;
+
+        if block.header.time >= before {
+            continue;
+        }
+        blocks += 1;
+        let coinbase = block.txdata.first().into_iter().flat_map(|tx| &tx.output);
+        for address in coinbase.filter_map(|output| output_address(&output.script_pubkey)) {
+            let time = found
+                .entry(address.to_string())
+                .or_insert(block.header.time);
+            *time = (*time).min(block.header.time);
+        }
+    }
+
+    Ok(blocks)
+}
+
+/// The `YYYY-MM-DD` UTC date of a Unix time.
+pub fn date(time: u32) -> String {
+    // Howard Hinnant's civil_from_days.
+    let z = time as i64 / 86400 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The Unix time of the start of a `YYYY-MM-DD` UTC date.
+pub fn parse_date(date: &str) -> Option<u32> {
+    let mut fields = date.splitn(3, '-').map(|f| f.parse::<i64>().ok());
+    let (year, month, day) = (fields.next()??, fields.next()??, fields.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let year = year - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    u32::try_from(days * 86400).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::blockdata::constants::genesis_block;
+    use bitcoin::consensus::serialize;
+
+    const GENESIS: &str = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa";
+
+    #[test]
+    fn check_early_blocks() {
+        assert_eq!(date(1231006505), "2009-01-03");
+        assert_eq!(parse_date("2011-01-01"), Some(BEFORE_2011));
+        assert_eq!(date(BEFORE_2011 - 1), "2010-12-31");
+        assert_eq!(parse_date("2011-13-01"), None);
+
+        let dir = std::env::temp_dir().join("cwins-check-early");
+        std::fs::create_dir_all(&dir).unwrap();
+        let block = serialize(&genesis_block(Network::Bitcoin));
+        let mut blk = Network::Bitcoin.magic().to_bytes().to_vec();
+        blk.extend((block.len() as u32).to_le_bytes());
+        blk.extend(block);
+        blk.extend([0; 16]);
+        let path = dir.join("blk00000.dat");
+
+        std::fs::write(&path, &blk).unwrap();
+        let mut found = BTreeMap::new();
+        assert_eq!(scan_blk_file(&path, BEFORE_2011, &mut found).unwrap(), 1);
+        assert_eq!(found[GENESIS], 1231006505);
+        assert_eq!(
+            scan_blk_file(&path, 1231006505, &mut BTreeMap::new()).unwrap(),
+            0
+        );
+
+        // The same file obfuscated like Bitcoin Core 28 does.
+        let key = [0x5a, 0x13, 0x00, 0xff, 0x42, 0x07, 0x99, 0x01];
+        let obfuscated: Vec<u8> = blk
+            .iter()
+            .zip(key.iter().cycle())
+            .map(|(b, k)| b ^ k)
+            .collect();
+        std::fs::write(&path, obfuscated).unwrap();
+        std::fs::write(dir.join("xor.dat"), key).unwrap();
+        let mut found = BTreeMap::new();
+        assert_eq!(scan_blk_file(&path, BEFORE_2011, &mut found).unwrap(), 1);
+        assert!(found.contains_key(GENESIS));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let early =
+            EarlyAddresses::parse(&format!("# coinbases\n{} 2009-01-03\n\n1Other\n", GENESIS));
+        assert_eq!(early.len(), 2);
+        assert_eq!(
+            early.find(GENESIS, None),
+            Some((GENESIS.to_string(), "2009-01-03"))
+        );
+        assert_eq!(early.find("1Other", None), Some(("1Other".to_string(), "")));
+        assert_eq!(early.find("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", None), None);
+
+        // A segwit address of a key that mined, compressed.
+        let pubkey: PublicKey = "04678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5f"
+            .parse()
+            .unwrap();
+        let compressed = PublicKey::new(pubkey.inner);
+        let segwit = Address::p2wpkh(&compressed, Network::Bitcoin).unwrap();
+        assert_eq!(
+            early.find(&segwit.to_string(), Some(&compressed)),
+            Some((GENESIS.to_string(), "2009-01-03"))
+        );
+    }
+}
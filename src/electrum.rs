@@ -0,0 +1,185 @@
+// Electrum protocol (electrs, Fulcrum, ElectrumX) client over plain TCP, only as much
+// of it as the history of an address needs: newline-delimited JSON-RPC with the
+// `server.version` handshake and `blockchain.scripthash.get_history`.
+
+use bitcoin::address::NetworkChecked;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::Address;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+/// How long to wait for an answer.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The confirmed and mempool transactions of an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct History {
+    pub transactions: usize,
+    /// Height of the first block with one of them, `None` without confirmed ones.
+    pub first_height: Option<u32>,
+}
+
+pub struct ElectrumClient {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    next_id: u64,
+}
+
+fn protocol_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The script hash Electrum indexes addresses by: SHA-256 of the output script, in
+/// reversed byte order.
+pub fn script_hash(address: &Address<NetworkChecked>) -> String {
+    let hash = sha256::Hash::hash(address.script_pubkey().as_bytes());
+    hash.to_byte_array()
+        .iter()
+        .rev()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+impl ElectrumClient {
+    /// Connects to `host:port` and negotiates the protocol version.
+    pub fn connect(server: &str) -> io::Result<Self> {
+        let writer = TcpStream::connect(server)?;
+        writer.set_read_timeout(Some(TIMEOUT))?;
+        let mut client = ElectrumClient {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+            next_id: 0,
+        };
+
+        client.call(
+            "server.version",
+            json!(["craigwrightisnotsatoshi-check", "1.4"]),
+        )?;
+        Ok(client)
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> io::Result<Value> {
+        self.next_id += 1;
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id,
+            "method": method,
+            "params": params,
+        });
+        writeln!(self.writer, "{}", request)?;
+
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let response: Value =
+                serde_json::from_str(&line).map_err(|e| protocol_error(e.to_string()))?;
+
+            // Skip notifications and stale answers.
+            if response["id"].as_u64() != Some(self.next_id) {
+                continue;
+            }
+            return match response.get("error") {
+                Some(error) if !error.is_null() => Err(protocol_error(
+                    error["message"]
+                        .as_str()
+                        .unwrap_or("unknown error")
+                        .to_string(),
+                )),
+                _ => Ok(response["result"].clone()),
+            };
+        }
+    }
+
+    /// The transactions of `address`.
+    pub fn history(&mut self, address: &Address<NetworkChecked>) -> io::Result<History> {
+        let result = self.call(
+            "blockchain.scripthash.get_history",
+            json!([script_hash(address)]),
+        )?;
+        let transactions = result
+            .as_array()
+            .ok_or_else(|| protocol_error("get_history didn't return a list".to_string()))?;
+
+        // Mempool transactions have a height of 0 or -1.
+        let first_height = transactions
+            .iter()
+            .filter_map(|tx| tx["height"].as_i64())
+            .filter(|height| *height > 0)
+            .min()
+            .map(|height| height as u32);
+
+        Ok(History {
+            transactions: transactions.len(),
+            first_height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::Network;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn check_electrum_history() {
+        // The example of the Electrum protocol documentation.
+        let address = "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        assert_eq!(
+            script_hash(&address),
+            "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161"
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_address = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut stream = stream;
+            let mut methods = Vec::new();
+            for answer in [
+                r#""result":["Fulcrum 1.9","1.4"]"#,
+                r#""result":[{"tx_hash":"aa","height":0},{"tx_hash":"bb","height":170},{"tx_hash":"cc","height":9}]"#,
+            ] {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let request: Value = serde_json::from_str(&line).unwrap();
+                methods.push(request["method"].as_str().unwrap().to_string());
+                writeln!(
+                    stream,
+                    r#"{{"jsonrpc":"2.0","method":"blockchain.headers.subscribe","params":[]}}"#
+                )
+                .unwrap();
+                writeln!(
+                    stream,
+                    r#"{{"jsonrpc":"2.0","id":{},{}}}"#,
+                    request["id"], answer
+                )
+                .unwrap();
+            }
+            methods
+        });
+
+        let mut client = ElectrumClient::connect(&server_address).unwrap();
+        assert_eq!(
+            client.history(&address).unwrap(),
+            History {
+                transactions: 3,
+                first_height: Some(9),
+            }
+        );
+        assert_eq!(
+            server.join().unwrap(),
+            ["server.version", "blockchain.scripthash.get_history"]
+        );
+    }
+}
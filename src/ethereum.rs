@@ -0,0 +1,143 @@
+// EIP-191 `personal_sign` proofs of Ethereum accounts (`--scheme ethereum`): the key
+// recovered from the signature over the keccak-256 hash of the prefixed message has to
+// be the one behind the `0x` address, the last 20 bytes of the keccak-256 of the key.
+
+use crate::signature::secp;
+use crate::VerifyError;
+use bitcoin::hashes::hex::FromHex;
+use bitcoin::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use bitcoin::secp256k1::{self, Message};
+use bitcoin::sign_message::MessageSignatureError;
+use sha3::{Digest, Keccak256};
+
+pub type EthAddress = [u8; 20];
+
+/// keccak-256 of `"\x19Ethereum Signed Message:\n" + <length of message> + message`.
+pub fn eth_msg_hash(message: impl AsRef<[u8]>) -> [u8; 32] {
+    let message = message.as_ref();
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()));
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+/// The address of `pubkey`: the last 20 bytes of the keccak-256 of its uncompressed
+/// point.
+pub fn eth_address(pubkey: &secp256k1::PublicKey) -> EthAddress {
+    let hash = Keccak256::digest(&pubkey.serialize_uncompressed()[1..]);
+    hash[12..].try_into().expect("20 bytes")
+}
+
+/// Whether `s` looks like an Ethereum address (`0x` and 40 hex digits), used to tell
+/// them apart in a mixed list.
+pub fn is_eth_address(s: &str) -> bool {
+    s.len() == 42 && s.starts_with("0x") && s[2..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// The EIP-55 mixed case spelling of `address`.
+pub fn to_checksum(address: &EthAddress) -> String {
+    let hex: String = address.iter().map(|b| format!("{:02x}", b)).collect();
+    let hash = Keccak256::digest(hex.as_bytes());
+    let checksummed: String = hex
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0xf;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+/// Parses a `0x` address, `None` if it isn't one. An address in mixed case has to
+/// carry a valid EIP-55 checksum, all lower or upper case ones have none.
+pub fn parse_eth_address(s: &str) -> Option<EthAddress> {
+    if !is_eth_address(s) {
+        return None;
+    }
+    let hex = &s[2..];
+    let address = Vec::from_hex(hex).ok()?.try_into().ok()?;
+    let mixed =
+        hex.bytes().any(|b| b.is_ascii_lowercase()) && hex.bytes().any(|b| b.is_ascii_uppercase());
+    if mixed && to_checksum(&address) != s {
+        return None;
+    }
+
+    Some(address)
+}
+
+/// Verifies a hex `r || s || v` `personal_sign` signature (`v` 27/28 or 0/1, an
+/// optional `0x` prefix) of `message` by `address`.
+pub fn check_ethereum_sig(
+    address: &EthAddress,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    let hex = signature.strip_prefix("0x").unwrap_or(signature);
+    let bytes = Vec::from_hex(hex)
+        .map_err(|_| MessageSignatureError::InvalidEncoding(secp256k1::Error::InvalidSignature))?;
+    if bytes.len() != 65 {
+        return Err(MessageSignatureError::InvalidLength.into());
+    }
+
+    let v = match bytes[64] {
+        v @ (27 | 28) => v - 27,
+        v @ (0 | 1) => v,
+        _ => {
+            return Err(
+                MessageSignatureError::InvalidEncoding(secp256k1::Error::InvalidRecoveryId).into(),
+            )
+        }
+    };
+    let recid = RecoveryId::from_i32(v as i32).map_err(MessageSignatureError::InvalidEncoding)?;
+    let signature = RecoverableSignature::from_compact(&bytes[..64], recid)
+        .map_err(MessageSignatureError::InvalidEncoding)?;
+    let msg = Message::from_slice(&eth_msg_hash(message)).expect("32 bytes");
+    let pubkey = secp()
+        .recover_ecdsa(&msg, &signature)
+        .map_err(MessageSignatureError::InvalidEncoding)?;
+
+    Ok(eth_address(&pubkey) == *address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_ethereum_signatures() {
+        // The `web3.eth.accounts.sign` example of the web3.js documentation.
+        let address = parse_eth_address("0x2c7536E3605D9C16a7a3D7b1898e529396a65c23").unwrap();
+        let signature = "0xb91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c";
+        assert_eq!(
+            hex(&eth_msg_hash("Some data")),
+            "1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655"
+        );
+
+        assert_eq!(
+            check_ethereum_sig(&address, "Some data", signature).ok(),
+            Some(true)
+        );
+        assert_eq!(
+            check_ethereum_sig(&address, "Some other data", signature).ok(),
+            Some(false)
+        );
+        assert!(check_ethereum_sig(&address, "Some data", "0xb914").is_err());
+
+        // A broken checksum isn't an address, all lower case has none to check.
+        assert!(parse_eth_address("0x2c7536e3605D9C16a7a3D7b1898e529396a65c23").is_none());
+        assert_eq!(
+            parse_eth_address("0x2c7536e3605d9c16a7a3d7b1898e529396a65c23"),
+            Some(address)
+        );
+        assert!(!is_eth_address("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"));
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
@@ -0,0 +1,110 @@
+// Live progress for GUIs and web frontends (`--events-socket PATH`): one JSON object
+// per line for every line that starts being verified, every verdict and the summary,
+// written to the Unix socket the frontend listens on or to a named pipe it reads.
+//
+//     {"event":"line","file":"list.txt","line":1,"address":"1FbPLPR..."}
+//     {"event":"verdict","address":"1FbPLPR...","result":"valid","status":"OK",...}
+//     {"event":"summary","lines":1,"valid":1,...}
+
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub struct Events {
+    out: Option<BufWriter<Box<dyn Write>>>,
+}
+
+impl Events {
+    /// Connects to the socket at `path`, or opens it for writing if it is a named pipe
+    /// or a plain file.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if std::fs::metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+                let stream = std::os::unix::net::UnixStream::connect(path)?;
+                return Ok(Events::new(Box::new(stream)));
+            }
+        }
+
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(Events::new(Box::new(file)))
+    }
+
+    pub fn new(out: Box<dyn Write>) -> Self {
+        Events {
+            out: Some(BufWriter::new(out)),
+        }
+    }
+
+    /// Sends `event` with its `"event"` name added. A frontend that went away doesn't
+    /// stop the run: the first failure is returned and the later events are dropped.
+    pub fn send(&mut self, name: &str, mut event: serde_json::Value) -> io::Result<()> {
+        let Some(out) = &mut self.out else {
+            return Ok(());
+        };
+        if let Some(fields) = event.as_object_mut() {
+            fields.insert("event".to_string(), name.into());
+        }
+
+        let sent = writeln!(out, "{}", event).and_then(|()| out.flush());
+        if sent.is_err() {
+            self.out = None;
+        }
+        sent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Keeps what was written, fails once `full`.
+    #[derive(Clone, Default)]
+    struct Frontend {
+        written: Rc<RefCell<Vec<u8>>>,
+        full: Rc<RefCell<bool>>,
+    }
+
+    impl Write for Frontend {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if *self.full.borrow() {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.written.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn check_events() {
+        let frontend = Frontend::default();
+        let mut events = Events::new(Box::new(frontend.clone()));
+        events
+            .send("line", serde_json::json!({"line": 1, "address": "1A"}))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(frontend.written.borrow().clone()).unwrap(),
+            "{\"address\":\"1A\",\"event\":\"line\",\"line\":1}\n"
+        );
+
+        *frontend.full.borrow_mut() = true;
+        assert!(events.send("summary", serde_json::json!({})).is_err());
+        *frontend.full.borrow_mut() = false;
+        events.send("summary", serde_json::json!({})).unwrap();
+        assert_eq!(
+            frontend
+                .written
+                .borrow()
+                .iter()
+                .filter(|&&b| b == b'\n')
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,115 @@
+// C interface of the `cdylib`, declared in `include/cwins.h`:
+//
+//     #include "cwins.h"
+//
+//     int rc = cwins_verify("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", NULL, "G3Ss...");
+//     if (rc == CWINS_VALID) { ... }
+//
+// All strings are NUL-terminated UTF-8 and only borrowed for the duration of the call.
+
+use crate::{verify_message_on, VerifyError, MESSAGE};
+use bitcoin::Network;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+
+pub const CWINS_VALID: c_int = 1;
+pub const CWINS_INVALID: c_int = 0;
+pub const CWINS_ERROR_ARGUMENT: c_int = -1;
+pub const CWINS_ERROR_ADDRESS: c_int = -2;
+pub const CWINS_ERROR_ENCODING: c_int = -3;
+pub const CWINS_ERROR_HEADER: c_int = -4;
+pub const CWINS_ERROR_SIGNATURE: c_int = -5;
+
+fn error_code(error: &VerifyError) -> c_int {
+    match error {
+        VerifyError::AddressParseError(_) => CWINS_ERROR_ADDRESS,
+        VerifyError::SignatureBase64DecodeError(_) | VerifyError::SignatureZBase32DecodeError => {
+            CWINS_ERROR_ENCODING
+        }
+        VerifyError::InvalidHeader(_) | VerifyError::HeaderMismatch(_) => CWINS_ERROR_HEADER,
+        VerifyError::GeneralSignatureProblem(_)
+        | VerifyError::InvalidBip322Proof(_)
+        | VerifyError::NonCanonical => CWINS_ERROR_SIGNATURE,
+    }
+}
+
+/// `None` for a NULL pointer or a string that isn't UTF-8.
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Checks a base64 signature of `message` (the built-in one if NULL) by a mainnet
+/// address. Returns `CWINS_VALID`, `CWINS_INVALID` or a negative `CWINS_ERROR_*`.
+///
+/// # Safety
+///
+/// Every non-NULL pointer must point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn cwins_verify(
+    address: *const c_char,
+    message: *const c_char,
+    sig_b64: *const c_char,
+) -> c_int {
+    let message = if message.is_null() {
+        Some(MESSAGE)
+    } else {
+        to_str(message)
+    };
+
+    match (to_str(address), message, to_str(sig_b64)) {
+        (Some(address), Some(message), Some(signature)) => {
+            match verify_message_on(Network::Bitcoin, address, message, signature) {
+                Ok(true) => CWINS_VALID,
+                Ok(false) => CWINS_INVALID,
+                Err(e) => error_code(&e),
+            }
+        }
+        _ => CWINS_ERROR_ARGUMENT,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::ptr;
+
+    #[test]
+    fn check_cwins_verify() {
+        let address = CString::new("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m").unwrap();
+        let signature = CString::new("G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=").unwrap();
+        let other = CString::new("other").unwrap();
+        let garbage = CString::new("1Fb").unwrap();
+
+        unsafe {
+            assert_eq!(
+                cwins_verify(address.as_ptr(), ptr::null(), signature.as_ptr()),
+                CWINS_VALID
+            );
+            assert_eq!(
+                cwins_verify(address.as_ptr(), other.as_ptr(), signature.as_ptr()),
+                CWINS_INVALID
+            );
+            assert_eq!(
+                cwins_verify(garbage.as_ptr(), ptr::null(), signature.as_ptr()),
+                CWINS_ERROR_ADDRESS
+            );
+            assert_eq!(
+                cwins_verify(address.as_ptr(), ptr::null(), garbage.as_ptr()),
+                CWINS_ERROR_ENCODING
+            );
+            assert_eq!(
+                cwins_verify(ptr::null(), ptr::null(), signature.as_ptr()),
+                CWINS_ERROR_ARGUMENT
+            );
+        }
+
+        let header = include_str!("../include/cwins.h");
+        assert!(header.contains(
+            "int cwins_verify(const char *address, const char *message, const char *sig_b64);"
+        ));
+    }
+}
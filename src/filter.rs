@@ -0,0 +1,68 @@
+// Restricting a run to some of the listed addresses (`--filter-address`,
+// `--filter-file`), e.g. to re-check only the ones disputed somewhere. The other
+// lines are not verified at all.
+
+use crate::input::is_comment;
+use regex::Regex;
+use std::collections::HashSet;
+
+/// The addresses to verify: matching the pattern and listed, of the criteria given.
+#[derive(Debug, Default)]
+pub struct AddressFilter {
+    pattern: Option<Regex>,
+    addresses: Option<HashSet<String>>,
+}
+
+impl AddressFilter {
+    /// Only addresses matching `pattern` (anywhere, anchor it with `^` for a prefix).
+    pub fn pattern(mut self, pattern: Regex) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    /// Only the addresses of a list, one per line; blank lines and `#` comments are
+    /// ignored.
+    pub fn list(mut self, list: &str) -> Self {
+        let addresses = list
+            .lines()
+            .filter(|line| !is_comment(line))
+            .map(|line| line.trim().to_string());
+        self.addresses = Some(addresses.collect());
+        self
+    }
+
+    /// Whether no address is filtered out.
+    pub fn is_empty(&self) -> bool {
+        self.pattern.is_none() && self.addresses.is_none()
+    }
+
+    pub fn matches(&self, address: &str) -> bool {
+        self.pattern.as_ref().is_none_or(|p| p.is_match(address))
+            && self.addresses.as_ref().is_none_or(|a| a.contains(address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_address_filter() {
+        let filter = AddressFilter::default();
+        assert!(filter.is_empty());
+        assert!(filter.matches("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"));
+
+        let filter = AddressFilter::default().pattern(Regex::new("^1Fb").unwrap());
+        assert!(filter.matches("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"));
+        assert!(!filter.matches("12cFuwo1i3FMhkmJoCN8D4SXaN2DQ6iBS"));
+
+        let filter = filter.list(
+            "# disputed\n1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m\n\n 1Fbother \n12cFuwo1i3FMhkmJoCN8D4SXaN2DQ6iBS\n",
+        );
+        assert!(!filter.is_empty());
+        assert!(filter.matches("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"));
+        assert!(filter.matches("1Fbother"));
+        assert!(!filter.matches("1FbUnlisted"));
+        assert!(!filter.matches("12cFuwo1i3FMhkmJoCN8D4SXaN2DQ6iBS"));
+    }
+}
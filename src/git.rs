@@ -0,0 +1,124 @@
+// Messages committed to a git repository (`--message-file git:<ref>:<path>`), to check
+// that the addresses signed one version of a file. The blob is read with `git
+// cat-file` from the repository the tool runs in.
+
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GitError {
+    #[error("cannot run {0}: {1}")]
+    Run(String, #[source] io::Error),
+    #[error("{0} is not in a git repository")]
+    NotARepository(String),
+    #[error("no git ref {0}")]
+    UnknownRef(String),
+    #[error("no {path} at {reference}")]
+    MissingPath { reference: String, path: String },
+}
+
+/// The ref and the path of a `git:<ref>:<path>` locator.
+pub fn parse_locator(locator: &str) -> Option<(&str, &str)> {
+    let (reference, path) = locator.strip_prefix("git:")?.split_once(':')?;
+    (!reference.is_empty() && !path.is_empty()).then_some((reference, path))
+}
+
+pub struct Git<'a> {
+    program: &'a Path,
+    repository: &'a Path,
+}
+
+impl<'a> Git<'a> {
+    /// Runs `program` (usually `git`) in `repository`.
+    pub fn new(program: &'a Path, repository: &'a Path) -> Self {
+        Git {
+            program,
+            repository,
+        }
+    }
+
+    fn run(&self, args: &[&str]) -> Result<std::process::Output, GitError> {
+        Command::new(self.program)
+            .args(args)
+            .current_dir(self.repository)
+            .output()
+            .map_err(|e| GitError::Run(self.program.display().to_string(), e))
+    }
+
+    /// The content of `path` at `reference`, e.g. a branch, tag or commit.
+    pub fn read_blob(&self, reference: &str, path: &str) -> Result<Vec<u8>, GitError> {
+        let output = self.run(&["cat-file", "-p", &format!("{}:{}", reference, path)])?;
+        if output.status.success() {
+            return Ok(output.stdout);
+        }
+
+        // Tell why from the steps of the lookup.
+        if !self.run(&["rev-parse", "--git-dir"])?.status.success() {
+            return Err(GitError::NotARepository(
+                self.repository.display().to_string(),
+            ));
+        }
+        let commit = format!("{}^{{commit}}", reference);
+        if !self
+            .run(&["rev-parse", "--verify", "--quiet", &commit])?
+            .status
+            .success()
+        {
+            return Err(GitError::UnknownRef(reference.to_string()));
+        }
+        Err(GitError::MissingPath {
+            reference: reference.to_string(),
+            path: path.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_read_blob() {
+        assert_eq!(
+            parse_locator("git:v1.0:message.txt"),
+            Some(("v1.0", "message.txt"))
+        );
+        assert_eq!(parse_locator("git:HEAD"), None);
+        assert_eq!(parse_locator("message.txt"), None);
+
+        let repository = std::env::temp_dir().join("cwins-check-git");
+        let _ = std::fs::remove_dir_all(&repository);
+        std::fs::create_dir_all(&repository).unwrap();
+        std::fs::write(repository.join("message.txt"), "We are all Satoshi\n").unwrap();
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "git init -q && git add message.txt && \
+                 git -c user.name=t -c user.email=t@example.org commit -qm message",
+            )
+            .current_dir(&repository)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let git = Git::new(Path::new("git"), &repository);
+        assert_eq!(
+            git.read_blob("HEAD", "message.txt").unwrap(),
+            b"We are all Satoshi\n"
+        );
+        assert!(matches!(
+            git.read_blob("no-such-branch", "message.txt"),
+            Err(GitError::UnknownRef(r)) if r == "no-such-branch"
+        ));
+        assert!(matches!(
+            git.read_blob("HEAD", "other.txt"),
+            Err(GitError::MissingPath { .. })
+        ));
+        assert!(matches!(
+            Git::new(Path::new("/nonexistent/git"), &repository).read_blob("HEAD", "message.txt"),
+            Err(GitError::Run(..))
+        ));
+        std::fs::remove_dir_all(&repository).unwrap();
+    }
+}
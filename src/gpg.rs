@@ -0,0 +1,164 @@
+// Checking the PGP signature the list is distributed with (`--gpg-sig list.txt.asc
+// --gpg-key <key id>`) before any line is verified. GnuPG does the checking with
+// the keys of the user's keyring, its `--status-fd` lines tell which key signed.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GpgError {
+    #[error("cannot run {0}: {1}")]
+    Run(String, #[source] std::io::Error),
+    #[error("bad signature by {0}")]
+    Bad(String),
+    #[error("cannot check the signature: {0}")]
+    Unchecked(String),
+    #[error("signed by {actual}, not by {expected}")]
+    WrongKey { expected: String, actual: String },
+}
+
+/// A key id, long id or fingerprint as the hex digits only, upper case.
+fn normalize_key(key: &str) -> String {
+    key.trim_start_matches("0x")
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+/// The fingerprints of the key that made a good signature (the signing key, then
+/// its primary key) from GnuPG's status lines.
+fn signers(status: &str) -> Result<Vec<String>, GpgError> {
+    let mut signers = Vec::new();
+    for line in status.lines() {
+        let fields: Vec<&str> = line.split(' ').collect();
+        match fields[..] {
+            ["[GNUPG:]", "BADSIG", key, ..] => return Err(GpgError::Bad(key.to_string())),
+            ["[GNUPG:]", "ERRSIG", key, .., "9", _] => {
+                return Err(GpgError::Unchecked(format!("no public key {}", key)))
+            }
+            ["[GNUPG:]", "ERRSIG", key, ..] => {
+                return Err(GpgError::Unchecked(format!("error with key {}", key)))
+            }
+            ["[GNUPG:]", status @ ("EXPKEYSIG" | "REVKEYSIG" | "EXPSIG"), key, ..] => {
+                return Err(GpgError::Unchecked(format!("{} by {}", status, key)))
+            }
+            ["[GNUPG:]", "VALIDSIG", fingerprint, ..] => {
+                signers.push(fingerprint.to_string());
+                // The fingerprint of the primary key ends the line, if gpg knows it.
+                if let Some(primary) = fields.get(11) {
+                    signers.push(primary.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    if signers.is_empty() {
+        return Err(GpgError::Unchecked("no signature found".to_string()));
+    }
+
+    Ok(signers)
+}
+
+pub struct Gpg {
+    program: PathBuf,
+}
+
+impl Gpg {
+    /// Runs `program` (usually `gpg`).
+    pub fn new(program: PathBuf) -> Self {
+        Gpg { program }
+    }
+
+    /// Checks that `signature` is a good detached signature of `file` by `key` (a key
+    /// id or fingerprint, of the signing subkey or its primary key) and returns the
+    /// fingerprint of the key that signed.
+    pub fn verify_detached(
+        &self,
+        signature: &Path,
+        file: &Path,
+        key: &str,
+    ) -> Result<String, GpgError> {
+        let output = Command::new(&self.program)
+            .args(["--batch", "--no-tty", "--status-fd", "1", "--verify"])
+            .arg(signature)
+            .arg(file)
+            .output()
+            .map_err(|e| GpgError::Run(self.program.display().to_string(), e))?;
+
+        let signers = signers(&String::from_utf8_lossy(&output.stdout))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(GpgError::Unchecked(stderr.trim().to_string()));
+        }
+        let expected = normalize_key(key);
+        if expected.is_empty() || !signers.iter().any(|f| f.ends_with(&expected)) {
+            return Err(GpgError::WrongKey {
+                expected: key.to_string(),
+                actual: signers[0].clone(),
+            });
+        }
+
+        Ok(signers[0].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALIDSIG: &str = "[GNUPG:] NEWSIG\n\
+        [GNUPG:] GOODSIG 4F25E3B6CBC27A61 Maintainer <m@example.org>\n\
+        [GNUPG:] VALIDSIG 51A9E3E5D7F1D0A8C6B1A2F34F25E3B6CBC27A61 2024-05-20 1716200000 0 4 0 22 10 00 \
+        0123456789ABCDEF0123456789ABCDEF01234567\n";
+
+    #[test]
+    fn check_signers() {
+        let signers = signers(VALIDSIG).unwrap();
+        assert_eq!(
+            signers,
+            [
+                "51A9E3E5D7F1D0A8C6B1A2F34F25E3B6CBC27A61",
+                "0123456789ABCDEF0123456789ABCDEF01234567"
+            ]
+        );
+        assert!(signers[0].ends_with(&normalize_key("0x4f25e3b6cbc27a61")));
+        assert_eq!(normalize_key("51A9 E3E5 D7F1"), "51A9E3E5D7F1");
+
+        assert!(matches!(
+            super::signers("[GNUPG:] BADSIG 4F25E3B6CBC27A61 Maintainer\n"),
+            Err(GpgError::Bad(_))
+        ));
+        assert!(matches!(
+            super::signers("[GNUPG:] ERRSIG 4F25E3B6CBC27A61 22 10 00 1716200000 9 -\n"),
+            Err(GpgError::Unchecked(e)) if e == "no public key 4F25E3B6CBC27A61"
+        ));
+        assert!(super::signers("").is_err());
+    }
+
+    /// A stand-in for gpg answering with a good signature by the key above.
+    #[cfg(unix)]
+    #[test]
+    fn check_verify_detached() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = std::env::temp_dir().join("cwins-check-fake-gpg");
+        std::fs::write(&script, format!("#!/bin/sh\nprintf '{}'\n", VALIDSIG)).unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let gpg = Gpg::new(script.clone());
+        let (signature, file) = (Path::new("list.txt.asc"), Path::new("list.txt"));
+        assert_eq!(
+            gpg.verify_detached(signature, file, "CBC27A61").unwrap(),
+            "51A9E3E5D7F1D0A8C6B1A2F34F25E3B6CBC27A61"
+        );
+        assert!(gpg
+            .verify_detached(signature, file, "0123456789ABCDEF0123456789ABCDEF01234567")
+            .is_ok());
+        assert!(matches!(
+            gpg.verify_detached(signature, file, "DEADBEEF"),
+            Err(GpgError::WrongKey { .. })
+        ));
+        std::fs::remove_file(&script).unwrap();
+    }
+}
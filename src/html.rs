@@ -0,0 +1,191 @@
+// Standalone HTML report (`--output-format html`) for publishing the results: the
+// totals as a bar chart, the failed lines first and a table of all of them, sortable
+// by clicking a column header. Styles and the sorting script are inline, the page
+// loads nothing else.
+
+use serde_json::Value;
+
+/// Escapes text for HTML element content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}
+pre{background:#f4f4f4;padding:1em;white-space:pre-wrap}
+table{border-collapse:collapse;width:100%;margin-bottom:2em}
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left}
+th{background:#eee;cursor:pointer;user-select:none}
+td.sig{font-family:monospace;font-size:.8em;word-break:break-all}
+tr.invalid td{background:#fde2e2}
+tr.error td{background:#fff4d6}
+.legend span{display:inline-block;width:1em;height:1em;margin:0 .3em 0 1em;vertical-align:middle}";
+
+const SCRIPT: &str = "document.querySelectorAll('table.results').forEach(function(table){
+  table.querySelectorAll('th').forEach(function(th,column){
+    th.addEventListener('click',function(){
+      var body=table.tBodies[0],rows=Array.from(body.rows);
+      var ascending=th.dataset.order!=='asc';
+      rows.sort(function(a,b){
+        var x=a.cells[column].textContent,y=b.cells[column].textContent;
+        return ascending?x.localeCompare(y):y.localeCompare(x);
+      });
+      th.dataset.order=ascending?'asc':'desc';
+      rows.forEach(function(row){body.appendChild(row)});
+    });
+  });
+});";
+
+/// Result names of the JSON rows with their label and chart colour.
+const CLASSES: [(&str, &str, &str); 3] = [
+    ("valid", "OK", "#4caf50"),
+    ("invalid", "BAD", "#e53935"),
+    ("error", "ERROR", "#fbc02d"),
+];
+
+fn label(result: &str) -> &'static str {
+    CLASSES
+        .iter()
+        .find(|(name, _, _)| *name == result)
+        .map_or("ERROR", |(_, label, _)| label)
+}
+
+fn table(rows: &[&Value]) -> String {
+    let mut html = String::from(
+        "<table class=\"results\">\n<thead><tr><th>Address</th><th>Result</th><th>Error</th><th>Signature</th></tr></thead>\n<tbody>\n",
+    );
+    for row in rows {
+        let field = |name: &str| escape(row[name].as_str().unwrap_or_default());
+        let result = row["result"].as_str().unwrap_or("error");
+        html.push_str(&format!(
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td class=\"sig\">{}</td></tr>\n",
+            escape(result),
+            field("address"),
+            label(result),
+            field("error"),
+            field("signature"),
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html
+}
+
+/// The whole page for the JSON rows (the objects of `--output-format json`) of the
+/// lines verified against `message`.
+pub fn render(message: &str, rows: &[Value]) -> String {
+    let counts: Vec<usize> = CLASSES
+        .iter()
+        .map(|(name, _, _)| {
+            rows.iter()
+                .filter(|row| row["result"].as_str() == Some(name))
+                .count()
+        })
+        .collect();
+    let total = rows.len().max(1);
+
+    // One stacked bar with the share of each result.
+    let mut chart = String::from(
+        "<svg width=\"100%\" height=\"32\" viewBox=\"0 0 1000 32\" preserveAspectRatio=\"none\">",
+    );
+    let mut x = 0;
+    for ((_, _, color), count) in CLASSES.iter().zip(&counts) {
+        let width = count * 1000 / total;
+        chart.push_str(&format!(
+            "<rect x=\"{}\" width=\"{}\" height=\"32\" fill=\"{}\"/>",
+            x, width, color
+        ));
+        x += width;
+    }
+    chart.push_str("</svg>\n<p class=\"legend\">");
+    for ((_, label, color), count) in CLASSES.iter().zip(&counts) {
+        chart.push_str(&format!(
+            "<span style=\"background:{}\"></span>{} {}",
+            color, label, count
+        ));
+    }
+    chart.push_str("</p>\n");
+
+    let failed: Vec<&Value> = rows
+        .iter()
+        .filter(|row| row["result"].as_str() != Some("valid"))
+        .collect();
+    let all: Vec<&Value> = rows.iter().collect();
+    let failures = if failed.is_empty() {
+        "<p>Every signature is valid.</p>\n".to_string()
+    } else {
+        table(&failed)
+    };
+
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\">
+<title>Signature verification report</title>
+<style>
+{}
+</style>
+</head>
+<body>
+<h1>Signature verification report</h1>
+<p>{} signatures of the message:</p>
+<pre>{}</pre>
+<h2>Summary</h2>
+{}<h2>Failed ({})</h2>
+{}<h2>All results</h2>
+{}<script>
+{}
+</script>
+</body>
+</html>
+",
+        STYLE,
+        rows.len(),
+        escape(message),
+        chart,
+        failed.len(),
+        failures,
+        table(&all),
+        SCRIPT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn check_html_report() {
+        let rows = [
+            json!({"address": "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", "signature": "G3Ss", "result": "valid", "error": null}),
+            json!({"address": "1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu", "signature": "HFjd", "result": "invalid", "error": null}),
+            json!({"address": "<script>", "signature": "x", "result": "error", "error": "address_parse"}),
+        ];
+        let page = render("We are all <Satoshi>", &rows);
+
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("<pre>We are all &lt;Satoshi&gt;</pre>"));
+        assert!(page.contains("<h2>Failed (2)</h2>"));
+        assert!(page.contains("OK 1"));
+        assert!(page.contains(
+            "<tr class=\"invalid\"><td>1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu</td><td>BAD</td>"
+        ));
+        assert!(page.contains("<td>&lt;script&gt;</td><td>ERROR</td><td>address_parse</td>"));
+        // Nothing is loaded from elsewhere.
+        assert!(!page.contains("src="));
+        assert!(!page.contains("href="));
+
+        assert!(render("hi", &[]).contains("Every signature is valid."));
+    }
+}
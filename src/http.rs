@@ -0,0 +1,386 @@
+// Minimal HTTP/1.1 front-end for web pages: `POST /verify` with a JSON body
+//
+//     {"address": "1Fb...", "signature": "G3Ss...", "message": "optional"}
+//
+// is answered with
+//
+//     {"schema_version": 1, "result": "valid" | "invalid" | "error", "error": <code> | null}
+//
+// where the error codes are the ones of the JSON output. `GET /metrics` serves the
+// Prometheus metrics. Every connection serves a single request, there is no
+// keep-alive. A client gets a few seconds to send its request and read the answer,
+// and only so many are served at a time, the ones above that get a 503.
+
+use crate::metrics::Metrics;
+use crate::{check_sig, SCHEMA_VERSION};
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::{Address, Network};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+/// Request bodies longer than this are refused.
+const MAX_BODY_LEN: usize = 64 * 1024;
+/// Request lines and headers longer than this in total are refused, without reading
+/// past it.
+const MAX_HEADER_LEN: usize = 16 * 1024;
+/// How long a read or a write of a connection may wait for the client.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections served at the same time.
+const MAX_CONNECTIONS: usize = 64;
+
+/// The connections being served, at most `limit` of them.
+struct Connections {
+    active: Arc<AtomicUsize>,
+    limit: usize,
+}
+
+/// A connection counted by [`Connections`] until it is dropped.
+struct Connection(Arc<AtomicUsize>);
+
+impl Connections {
+    fn new(limit: usize) -> Self {
+        Connections {
+            active: Arc::default(),
+            limit,
+        }
+    }
+
+    /// `None` while `limit` connections are being served.
+    fn open(&self) -> Option<Connection> {
+        self.active
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < self.limit).then_some(n + 1)
+            })
+            .ok()?;
+        Some(Connection(self.active.clone()))
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Status line and JSON body of the answer to a `POST /verify` body.
+pub fn verify_request(body: &[u8], message: &str, network: Network) -> (u16, Value) {
+    let request: Value = match serde_json::from_slice(body) {
+        Ok(v) => v,
+        Err(_) => return (400, json!({ "error": "invalid JSON" })),
+    };
+
+    let field = |name: &str| request.get(name).and_then(Value::as_str);
+    let (address, signature) = match (field("address"), field("signature")) {
+        (Some(a), Some(s)) => (a, s),
+        _ => {
+            return (
+                400,
+                json!({ "error": "address and signature are required" }),
+            )
+        }
+    };
+    let message = field("message").unwrap_or(message);
+
+    let result = match address.parse::<Address<NetworkUnchecked>>() {
+        Ok(a) => match a.require_network(network) {
+            Ok(a) => check_sig(a, message, signature).map_err(|e| e.code()),
+            Err(_) => Err("network"),
+        },
+        Err(_) => Err("address_parse"),
+    };
+
+    let body = json!({
+        "schema_version": SCHEMA_VERSION,
+        "result": match result {
+            Ok(true) => "valid",
+            Ok(false) => "invalid",
+            Err(_) => "error",
+        },
+        "error": result.err(),
+    });
+
+    (200, body)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+fn write_response(
+    stream: &mut impl Write,
+    status: u16,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn respond(stream: &mut impl Write, status: u16, body: &Value) -> io::Result<()> {
+    write_response(stream, status, "application/json", &body.to_string())
+}
+
+/// Reads one request and writes the response.
+pub fn handle_request(
+    stream: &mut (impl Read + Write),
+    message: &str,
+    network: Network,
+    metrics: &Metrics,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(&mut *stream);
+
+    // A line without an end stops at the limit instead of growing.
+    let mut head = (&mut reader).take(MAX_HEADER_LEN as u64);
+    let mut request_line = String::new();
+    head.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        let complete = head.read_line(&mut line)? > 0 && line.ends_with('\n');
+        if !complete && head.limit() == 0 {
+            drop(reader);
+            metrics.request(431);
+            return respond(stream, 431, &json!({ "error": "headers too long" }));
+        }
+        if !complete || line == "\r\n" || line == "\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    if path == "/metrics" && method == "GET" {
+        drop(reader);
+        // Rendered first, the scrape counts towards the next one.
+        let text = metrics.render();
+        metrics.request(200);
+        return write_response(stream, 200, "text/plain; version=0.0.4", &text);
+    }
+
+    let (status, body) = if path != "/verify" {
+        (404, json!({ "error": "not found" }))
+    } else if method != "POST" {
+        (405, json!({ "error": "use POST" }))
+    } else if content_length > MAX_BODY_LEN {
+        (413, json!({ "error": "body too long" }))
+    } else {
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body)?;
+        let started = Instant::now();
+        let (status, body) = verify_request(&body, message, network);
+        if let Some(result) = body["result"].as_str() {
+            metrics.verification(result, started.elapsed());
+        }
+        (status, body)
+    };
+
+    drop(reader);
+    metrics.request(status);
+    respond(stream, status, &body)
+}
+
+/// Serves `POST /verify` and `GET /metrics` on `listen` (`<host>:<port>`), one
+/// thread per connection up to [`MAX_CONNECTIONS`] of them.
+pub fn serve(listen: &str, message: &str, network: Network) -> io::Result<()> {
+    let listener = TcpListener::bind(listen)?;
+    info!("Listening on http://{}", listener.local_addr()?);
+    let metrics = Arc::new(Metrics::default());
+    let connections = Connections::new(MAX_CONNECTIONS);
+
+    for stream in listener.incoming() {
+        let mut stream: TcpStream = stream?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+        let Some(connection) = connections.open() else {
+            metrics.request(503);
+            if let Err(e) = respond(
+                &mut stream,
+                503,
+                &json!({ "error": "too many connections" }),
+            ) {
+                error!("Request failed: {}", e);
+            }
+            continue;
+        };
+        let message = message.to_string();
+        let metrics = metrics.clone();
+
+        thread::spawn(move || {
+            let _connection = connection;
+            if let Err(e) = handle_request(&mut stream, &message, network, &metrics) {
+                error!("Request failed: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MESSAGE;
+
+    fn request_with(raw: &str, metrics: Arc<Metrics>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            handle_request(&mut stream, MESSAGE, Network::Bitcoin, &metrics).unwrap();
+        });
+
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.write_all(raw.as_bytes()).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        server.join().unwrap();
+
+        response
+    }
+
+    fn request(raw: &str) -> String {
+        request_with(raw, Arc::default())
+    }
+
+    #[test]
+    fn check_verify_endpoint() {
+        let body = r#"{"address": "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", "signature": "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI="}"#;
+        let response = request(&format!(
+            "POST /verify HTTP/1.1\r\nHost: x\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        ));
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(r#"{"error":null,"result":"valid","schema_version":1}"#));
+
+        let response = request("GET /verify HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 405 "));
+        let response = request("GET / HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404 "));
+    }
+
+    #[test]
+    fn check_metrics_endpoint() {
+        let metrics = Arc::new(Metrics::default());
+        let body = r#"{"address": "1Fb", "signature": "x"}"#;
+        request_with(
+            &format!(
+                "POST /verify HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ),
+            metrics.clone(),
+        );
+
+        let response = request_with("GET /metrics HTTP/1.1\r\n\r\n", metrics);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/plain"));
+        assert!(response.contains("cwins_verifications_total{result=\"error\"} 1\n"));
+        assert!(response.contains("cwins_http_requests_total{status=\"200\"} 1\n"));
+        assert!(response.contains("cwins_verification_duration_seconds_count 1\n"));
+    }
+
+    /// A client sending `input`, keeping what it is answered.
+    struct Client<R> {
+        input: R,
+        output: Vec<u8>,
+    }
+
+    impl<R: Read> Read for Client<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl<R> Write for Client<R> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn check_limits() {
+        // An endless request line is cut off at the limit.
+        let mut client = Client {
+            input: io::repeat(b'a'),
+            output: Vec::new(),
+        };
+        handle_request(&mut client, MESSAGE, Network::Bitcoin, &Metrics::default()).unwrap();
+        assert!(client.output.starts_with(b"HTTP/1.1 431 "));
+
+        let header = format!("X-Padding: {}\r\n", "a".repeat(1000));
+        let mut client = Client {
+            input: io::Cursor::new(format!(
+                "GET /metrics HTTP/1.1\r\n{}\r\n",
+                header.repeat(20)
+            )),
+            output: Vec::new(),
+        };
+        handle_request(&mut client, MESSAGE, Network::Bitcoin, &Metrics::default()).unwrap();
+        assert!(client.output.starts_with(b"HTTP/1.1 431 "));
+
+        let connections = Connections::new(2);
+        let first = connections.open().unwrap();
+        let _second = connections.open().unwrap();
+        assert!(connections.open().is_none());
+        drop(first);
+        assert!(connections.open().is_some());
+    }
+
+    #[test]
+    fn check_verify_request() {
+        let (status, body) = verify_request(
+            br#"{"address": "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", "signature": "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=", "message": "other"}"#,
+            MESSAGE,
+            Network::Bitcoin,
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["result"], "invalid");
+
+        let (_, body) = verify_request(
+            br#"{"address": "1Fb", "signature": "x"}"#,
+            MESSAGE,
+            Network::Bitcoin,
+        );
+        assert_eq!(body["error"], "address_parse");
+
+        assert_eq!(verify_request(b"{", MESSAGE, Network::Bitcoin).0, 400);
+        assert_eq!(
+            verify_request(br#"{"address": "1Fb"}"#, MESSAGE, Network::Bitcoin).0,
+            400
+        );
+    }
+}
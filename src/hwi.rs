@@ -0,0 +1,187 @@
+// Signing on a hardware wallet (`sign --device trezor|ledger`) through the HWI
+// command line tool (https://github.com/bitcoin-core/HWI), which talks to the
+// devices over USB HID. The private key never leaves the device, the address to sign
+// for is shown and confirmed on its screen.
+
+use bitcoin::bip32::DerivationPath;
+use bitcoin::AddressType;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    Trezor,
+    Ledger,
+}
+
+impl FromStr for Device {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trezor" => Ok(Device::Trezor),
+            "ledger" => Ok(Device::Ledger),
+            _ => Err(format!("unknown device `{}`, expected trezor or ledger", s)),
+        }
+    }
+}
+
+impl Device {
+    /// The `--device-type` of HWI.
+    fn name(self) -> &'static str {
+        match self {
+            Device::Trezor => "trezor",
+            Device::Ledger => "ledger",
+        }
+    }
+}
+
+/// The BIP-44/49/84 path of the first receiving address of the first account.
+pub fn default_path(address_type: AddressType) -> DerivationPath {
+    let purpose = match address_type {
+        AddressType::P2sh => 49,
+        AddressType::P2wpkh => 84,
+        _ => 44,
+    };
+    format!("m/{}'/0'/0'/0/0", purpose)
+        .parse()
+        .expect("a valid derivation path")
+}
+
+/// The `--addr-type` of HWI for the address types `sign` supports.
+fn addr_type(address_type: AddressType) -> Result<&'static str, String> {
+    match address_type {
+        AddressType::P2pkh => Ok("legacy"),
+        AddressType::P2sh => Ok("sh_wit"),
+        AddressType::P2wpkh => Ok("wit"),
+        _ => Err(format!("{} addresses can't be signed for", address_type)),
+    }
+}
+
+pub struct Hwi {
+    program: PathBuf,
+    device: Device,
+}
+
+impl Hwi {
+    /// Runs `program` (usually `hwi`) for the first connected `device`.
+    pub fn new(program: PathBuf, device: Device) -> Self {
+        Hwi { program, device }
+    }
+
+    /// One HWI command and its JSON output, a JSON `error` is returned as the error.
+    fn run(&self, args: &[&str]) -> Result<Value, String> {
+        let output = Command::new(&self.program)
+            .arg("--device-type")
+            .arg(self.device.name())
+            .args(args)
+            .output()
+            .map_err(|e| format!("cannot run {}: {}", self.program.display(), e))?;
+
+        let answer: Value = serde_json::from_slice(&output.stdout).map_err(|_| {
+            format!(
+                "unexpected output of {}: {}",
+                self.program.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+        })?;
+        if let Some(error) = answer.get("error").and_then(Value::as_str) {
+            return Err(format!("{}: {}", self.device.name(), error));
+        }
+
+        Ok(answer)
+    }
+
+    /// Signs `message` with the key at `path` and returns the address of
+    /// `address_type` for it together with the base64 BIP-137 signature.
+    pub fn sign_message(
+        &self,
+        message: &str,
+        path: &DerivationPath,
+        address_type: AddressType,
+    ) -> Result<(String, String), String> {
+        let path = path.to_string();
+        let address = self.run(&[
+            "displayaddress",
+            "--path",
+            &path,
+            "--addr-type",
+            addr_type(address_type)?,
+        ])?;
+        let signature = self.run(&["signmessage", message, &path])?;
+
+        match (
+            address.get("address").and_then(Value::as_str),
+            signature.get("signature").and_then(Value::as_str),
+        ) {
+            (Some(address), Some(signature)) => Ok((address.to_string(), signature.to_string())),
+            _ => Err(format!(
+                "{} returned no address or signature",
+                self.device.name()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_defaults() {
+        assert_eq!(
+            default_path(AddressType::P2wpkh).to_string(),
+            "m/84'/0'/0'/0/0"
+        );
+        assert_eq!(
+            default_path(AddressType::P2pkh).to_string(),
+            "m/44'/0'/0'/0/0"
+        );
+        assert_eq!("ledger".parse(), Ok(Device::Ledger));
+        assert!("keepkey".parse::<Device>().is_err());
+        assert!(addr_type(AddressType::P2tr).is_err());
+    }
+
+    /// A stand-in for HWI answering like a device with the key of the list's first
+    /// address.
+    #[cfg(unix)]
+    #[test]
+    fn check_sign_with_hwi() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let script = std::env::temp_dir().join("cwins-check-fake-hwi");
+        std::fs::write(
+            &script,
+            r#"#!/bin/sh
+case "$3" in
+displayaddress) echo '{"address": "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"}' ;;
+signmessage) echo '{"signature": "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI="}' ;;
+*) echo '{"error": "Not a command", "code": -1}' ;;
+esac
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let hwi = Hwi::new(script.clone(), Device::Trezor);
+        let path = default_path(AddressType::P2pkh);
+        let (address, signature) = hwi
+            .sign_message(crate::MESSAGE, &path, AddressType::P2pkh)
+            .unwrap();
+        assert_eq!(address, "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m");
+        assert!(signature.starts_with("G3Ss"));
+        assert_eq!(
+            hwi.run(&["enumerate"]).unwrap_err(),
+            "trezor: Not a command"
+        );
+
+        let missing = Hwi::new(script.with_extension("missing"), Device::Ledger);
+        assert!(missing
+            .run(&["enumerate"])
+            .unwrap_err()
+            .starts_with("cannot run"));
+        std::fs::remove_file(&script).unwrap();
+    }
+}
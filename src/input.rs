@@ -0,0 +1,527 @@
+use crate::messages::unescape_message;
+use flate2::bufread::MultiGzDecoder;
+use memmap2::Mmap;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+/// One `<address> <signature>` entry to verify, `message` overrides the global one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub address: String,
+    pub signature: String,
+    pub message: Option<String>,
+    /// The fields after the signature (and the message), with [`LineFormat::extra`].
+    pub extra: Vec<String>,
+}
+
+/// How the fields of a text line are told apart, see [`LineFormat::parse`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineFormat {
+    /// The string between the fields, `None` for whitespace (or tabs before a message).
+    pub delimiter: Option<String>,
+    /// Keep the fields after the signature of a space separated line, or after the
+    /// message column of a delimited one, in [`Record::extra`] instead of rejecting
+    /// the line.
+    pub extra: bool,
+}
+
+/// A whitespace separated `<address> <signature>` line or a tab separated
+/// `<address>\t<signature>\t<message>` one, `None` for anything else.
+///
+/// The message may be wrapped in double quotes and uses the escapes of
+/// [`unescape_message`], so `"a \"quoted\"\nmessage"` is two lines.
+pub fn parse_line(line: &str) -> Option<Record> {
+    LineFormat::default().parse(line)
+}
+
+/// The column of a per-line message: empty for none, `""` for an empty one.
+fn message_column(message: &str) -> Option<String> {
+    match message.strip_prefix('"').and_then(|m| m.strip_suffix('"')) {
+        Some(quoted) => Some(unescape_message(quoted)),
+        None if message.is_empty() => None,
+        None => Some(unescape_message(message)),
+    }
+}
+
+impl LineFormat {
+    /// Like [`parse_line`]. With a delimiter the line is `<address>`, `<signature>`
+    /// and optionally `<message>` separated by it, and whitespace within the
+    /// signature (of one wrapped when it was pasted) is dropped.
+    pub fn parse(&self, line: &str) -> Option<Record> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let delimiter = self.delimiter.as_deref().unwrap_or("\t");
+        let mut columns = line.splitn(if self.extra { usize::MAX } else { 3 }, delimiter);
+        match (columns.next(), columns.next(), columns.next()) {
+            (Some(address), Some(signature), message) if self.delimiter.is_some() => {
+                let signature: String = signature.split_whitespace().collect();
+                if address.trim().is_empty() || signature.is_empty() {
+                    return None;
+                }
+                Some(Record {
+                    address: address.trim().to_string(),
+                    signature,
+                    message: message.and_then(message_column),
+                    extra: columns.map(|c| c.trim().to_string()).collect(),
+                })
+            }
+            (Some(address), Some(signature), Some(message)) => Some(Record {
+                address: address.trim().to_string(),
+                signature: signature.trim().to_string(),
+                message: message_column(message),
+                extra: columns.map(|c| c.trim().to_string()).collect(),
+            }),
+            _ if self.delimiter.is_some() => None,
+            _ => self.parse_whitespace(line),
+        }
+    }
+
+    fn parse_whitespace(&self, line: &str) -> Option<Record> {
+        let mut chunks = line.split_whitespace();
+        match (chunks.next(), chunks.next(), chunks.next()) {
+            (Some(address), Some(signature), None) => Some(Record {
+                address: address.to_string(),
+                signature: signature.to_string(),
+                message: None,
+                extra: Vec::new(),
+            }),
+            (Some(address), Some(signature), Some(first)) if self.extra => Some(Record {
+                address: address.to_string(),
+                signature: signature.to_string(),
+                message: None,
+                extra: std::iter::once(first)
+                    .chain(chunks)
+                    .map(str::to_string)
+                    .collect(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a line is blank or a `#` comment, skipped without a warning.
+pub fn is_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+/// A JSON object with `address`, `signature` and optionally `message` strings (the
+/// fields of the JSON output), `None` for anything else.
+pub fn parse_json_line(line: &str) -> Option<Record> {
+    let object: Value = serde_json::from_str(line).ok()?;
+    let field = |name: &str| object.get(name).and_then(Value::as_str).map(str::to_string);
+
+    Some(Record {
+        address: field("address")?,
+        signature: field("signature")?,
+        message: field("message"),
+        extra: Vec::new(),
+    })
+}
+
+/// A reader that waits for more data at the end of its input instead of ending,
+/// like `tail -f`.
+pub struct Follow<R> {
+    inner: R,
+    interval: Duration,
+}
+
+impl<R> Follow<R> {
+    /// Looks for appended data every `interval`.
+    pub fn new(inner: R, interval: Duration) -> Self {
+        Follow { inner, interval }
+    }
+}
+
+impl<R: Read> Read for Follow<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            thread::sleep(self.interval);
+        }
+    }
+}
+
+/// The file at `path` mapped into memory (`--mmap`), read without copying it into a
+/// buffer first. The file must not be truncated while it is mapped.
+pub fn map_file(path: &Path) -> io::Result<Cursor<Mmap>> {
+    let file = File::open(path)?;
+    // SAFETY: the mapping is only read, an input changing under it is documented as
+    // unsupported with --mmap.
+    let map = unsafe { Mmap::map(&file)? };
+    Ok(Cursor::new(map))
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// `reader`, decompressed on the fly if it starts with the gzip or zstd magic bytes.
+pub fn decompress(mut reader: Box<dyn BufRead + Send>) -> io::Result<Box<dyn BufRead + Send>> {
+    let head = reader.fill_buf()?;
+    if head.starts_with(GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else if head.starts_with(ZSTD_MAGIC) {
+        Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(
+            reader,
+        )?)))
+    } else {
+        Ok(reader)
+    }
+}
+
+/// Input formats [`detect_format`] tells apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Csv,
+    Ndjson,
+    Armored,
+    Html,
+}
+
+/// Guesses the format from the start of an input: armored if any line opens a signed
+/// message block, NDJSON if the first line is a JSON object, HTML if it is a tag, CSV
+/// if it is a comma separated header and text otherwise. Addresses and base64 signatures have no commas.
+/// Blank lines and comments before the first line don't count.
+pub fn detect_format(head: &str) -> Format {
+    if head.lines().any(|l| l.trim() == ARMOR_BEGIN) {
+        return Format::Armored;
+    }
+
+    match head.lines().map(str::trim).find(|l| !is_comment(l)) {
+        Some(line) if line.starts_with('{') => Format::Ndjson,
+        Some(line) if line.starts_with('<') => Format::Html,
+        Some(line) if !line.contains('\t') && line.contains(',') => Format::Csv,
+        _ => Format::Text,
+    }
+}
+
+/// Header names of the CSV columns holding each field. The message column is
+/// optional, rows with an empty message use the global one.
+#[derive(Debug, Clone)]
+pub struct CsvColumns {
+    pub address: String,
+    pub signature: String,
+    pub message: String,
+}
+
+/// Reads the records of a CSV file with a header row.
+pub fn read_csv(reader: impl Read, columns: &CsvColumns) -> Result<Vec<Record>, csv::Error> {
+    let mut reader = csv::Reader::from_reader(reader);
+
+    let headers = reader.headers()?.clone();
+    let position = |name: &str| headers.iter().position(|h| h == name);
+    let (address, signature) = match (position(&columns.address), position(&columns.signature)) {
+        (Some(a), Some(s)) => (a, s),
+        _ => {
+            return Err(csv::Error::from(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "CSV header needs `{}` and `{}` columns",
+                    columns.address, columns.signature
+                ),
+            )))
+        }
+    };
+    let message = position(&columns.message);
+
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        let field = |i: usize| row.get(i).unwrap_or_default().trim().to_string();
+
+        records.push(Record {
+            address: field(address),
+            signature: field(signature),
+            message: message.map(field).filter(|m| !m.is_empty()),
+            extra: Vec::new(),
+        });
+    }
+
+    Ok(records)
+}
+
+const ARMOR_BEGIN: &str = "-----BEGIN BITCOIN SIGNED MESSAGE-----";
+const ARMOR_SIGNATURE: [&str; 2] = [
+    "-----BEGIN SIGNATURE-----",
+    "-----BEGIN BITCOIN SIGNATURE-----",
+];
+
+/// The record of one armored block's signature section: either the bare address and
+/// signature lines or `Address: ...` style headers followed by the signature.
+fn armored_record(message: String, section: &[&str]) -> Option<Record> {
+    let mut address = None;
+    let mut lines = Vec::new();
+    for line in section.iter().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+        match line.split_once(": ") {
+            Some((name, value)) if name.eq_ignore_ascii_case("address") => {
+                address = Some(value.trim())
+            }
+            Some((name, _)) if name.eq_ignore_ascii_case("version") => {}
+            _ => lines.push(line),
+        }
+    }
+
+    let (address, signature) = match (address, &lines[..]) {
+        (Some(address), [signature]) => (address, *signature),
+        (None, [address, signature]) => (*address, *signature),
+        _ => return None,
+    };
+
+    Some(Record {
+        address: address.to_string(),
+        signature: signature.to_string(),
+        message: Some(message),
+        extra: Vec::new(),
+    })
+}
+
+/// Every `-----BEGIN BITCOIN SIGNED MESSAGE-----` block of `text`, `None` for the
+/// malformed ones. The message is the text between the armor lines without the
+/// final line break.
+pub fn parse_armored(text: &str) -> Vec<Option<Record>> {
+    let mut records = Vec::new();
+    let mut lines = text.lines().map(|l| l.trim_end_matches('\r'));
+
+    while lines.any(|l| l.trim() == ARMOR_BEGIN) {
+        let mut message = Vec::new();
+        let mut section = Vec::new();
+        let mut in_signature = false;
+        let mut closed = false;
+
+        for line in lines.by_ref() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("-----END ") {
+                closed = true;
+                break;
+            } else if !in_signature && ARMOR_SIGNATURE.contains(&trimmed) {
+                in_signature = true;
+            } else if in_signature {
+                section.push(line);
+            } else {
+                message.push(line);
+            }
+        }
+
+        records.push(if closed && in_signature {
+            armored_record(message.join("\n"), &section)
+        } else {
+            None
+        });
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_input_formats() {
+        assert_eq!(
+            parse_line("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m  G3Ss=\n"),
+            Some(Record {
+                address: "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m".to_string(),
+                signature: "G3Ss=".to_string(),
+                message: None,
+                extra: Vec::new(),
+            })
+        );
+        assert_eq!(parse_line("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"), None);
+        assert_eq!(parse_line("1A G3Ss= alice 2024-05-01"), None);
+        let extra = LineFormat {
+            extra: true,
+            ..LineFormat::default()
+        };
+        let record = extra.parse("1A G3Ss= alice 2024-05-01\n").unwrap();
+        assert_eq!(record.signature, "G3Ss=");
+        assert_eq!(record.extra, ["alice", "2024-05-01"]);
+        let record = extra.parse("1A\tG3Ss=\t\tthe alice\t2024-05-01").unwrap();
+        assert_eq!(
+            (record.message, record.extra),
+            (
+                None,
+                vec!["the alice".to_string(), "2024-05-01".to_string()]
+            )
+        );
+        assert!(extra.parse("1A G3Ss=").unwrap().extra.is_empty());
+
+        // A wrapped signature in a comma separated line.
+        let comma = LineFormat {
+            delimiter: Some(",".to_string()),
+            ..LineFormat::default()
+        };
+        let record = comma.parse("1A, G3Ss\n gKMK= ,We are all Satoshi").unwrap();
+        assert_eq!(
+            (record.address.as_str(), record.signature.as_str()),
+            ("1A", "G3SsgKMK=")
+        );
+        assert_eq!(record.message.as_deref(), Some("We are all Satoshi"));
+        assert_eq!(comma.parse("1A,G3Ss=").unwrap().message, None);
+        assert_eq!(comma.parse("1A G3Ss="), None);
+        let message = comma.parse("1A,G3Ss=,a, b").unwrap().message;
+        assert_eq!(message.as_deref(), Some("a, b"));
+        assert_eq!(comma.parse(",G3Ss="), None);
+        assert!(is_comment("  \r\n"));
+        assert!(is_comment("  # address, signature"));
+        assert!(!is_comment("1A G3Ss=  # trailing"));
+        assert_eq!(
+            detect_format("# address, signature\n\n1A G3Ss=\n"),
+            Format::Text
+        );
+
+        let record = parse_line("1A\tG3Ss=\tWe are all Satoshi").unwrap();
+        assert_eq!(record.signature, "G3Ss=");
+        assert_eq!(record.message.as_deref(), Some("We are all Satoshi"));
+        let record = parse_line("1A\tG3Ss=\t\"a \\\"quoted\\\"\\nmessage\"\r\n").unwrap();
+        assert_eq!(record.message.as_deref(), Some("a \"quoted\"\nmessage"));
+        let record = parse_line("1A\tG3Ss=").unwrap();
+        assert_eq!(record.message, None);
+        assert_eq!(parse_line("1A\tG3Ss=\t").unwrap().message, None);
+        assert_eq!(
+            parse_line("1A\tG3Ss=\t\"\"").unwrap().message.as_deref(),
+            Some("")
+        );
+
+        let columns = CsvColumns {
+            address: "addr".to_string(),
+            signature: "sig".to_string(),
+            message: "msg".to_string(),
+        };
+        let csv = "name,addr,sig,msg\na,1A,G3Ss=,\"hello, world\"\nb,1B,HFjd=,\n";
+        let records = read_csv(csv.as_bytes(), &columns).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].address, "1A");
+        assert_eq!(records[0].message.as_deref(), Some("hello, world"));
+        assert_eq!(records[1].signature, "HFjd=");
+        assert_eq!(records[1].message, None);
+
+        assert!(read_csv("address,signature\n1A,G3Ss=\n".as_bytes(), &columns).is_err());
+
+        let armored = "\
+-----BEGIN BITCOIN SIGNED MESSAGE-----
+We are all Satoshi
+
+twice
+-----BEGIN SIGNATURE-----
+1A
+G3Ss=
+-----END BITCOIN SIGNED MESSAGE-----
+text in between
+-----BEGIN BITCOIN SIGNED MESSAGE-----\r
+hello\r
+-----BEGIN BITCOIN SIGNATURE-----\r
+Version: Bitcoin-qt (1.0)\r
+Address: 1B\r
+\r
+HFjd=\r
+-----END BITCOIN SIGNATURE-----\r
+-----BEGIN BITCOIN SIGNED MESSAGE-----
+no signature
+-----END BITCOIN SIGNED MESSAGE-----
+";
+        let records = parse_armored(armored);
+        assert_eq!(records.len(), 3);
+        let first = records[0].as_ref().unwrap();
+        assert_eq!(first.address, "1A");
+        assert_eq!(
+            first.message.as_deref(),
+            Some("We are all Satoshi\n\ntwice")
+        );
+        let second = records[1].as_ref().unwrap();
+        assert_eq!(second.address, "1B");
+        assert_eq!(second.signature, "HFjd=");
+        assert_eq!(second.message.as_deref(), Some("hello"));
+        assert_eq!(records[2], None);
+
+        let record = parse_json_line(r#"{"address":"1A","signature":"G3Ss=","message":"hi"}"#);
+        assert_eq!(record.unwrap().message.as_deref(), Some("hi"));
+        assert_eq!(
+            parse_json_line(r#"{"address":"1A","signature":"G3Ss="}"#)
+                .unwrap()
+                .message,
+            None
+        );
+        assert_eq!(parse_json_line(r#"{"address":"1A"}"#), None);
+        assert_eq!(parse_json_line("1A G3Ss="), None);
+
+        assert_eq!(detect_format("1A G3Ss=\n1B HFjd=\n"), Format::Text);
+        assert_eq!(detect_format("1A\tG3Ss=\thello, world\n"), Format::Text);
+        assert_eq!(detect_format(""), Format::Text);
+        assert_eq!(
+            detect_format("\naddress,signature\n1A,G3Ss=\n"),
+            Format::Csv
+        );
+        assert_eq!(detect_format(r#"{"address":"1A"}"#), Format::Ndjson);
+        assert_eq!(detect_format(armored), Format::Armored);
+        assert_eq!(detect_format("<!DOCTYPE html>\n<html>\n"), Format::Html);
+    }
+
+    #[test]
+    fn check_follow() {
+        use std::fs::{File, OpenOptions};
+        use std::io::Write;
+
+        let path = std::env::temp_dir().join("cwins-check-follow-test.txt");
+        File::create(&path)
+            .unwrap()
+            .write_all(b"1A G3Ss=\n")
+            .unwrap();
+
+        let file = File::open(&path).unwrap();
+        let mut lines = BufReader::new(Follow::new(file, Duration::from_millis(10))).lines();
+        assert_eq!(lines.next().unwrap().unwrap(), "1A G3Ss=");
+
+        let writer = {
+            let path = path.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                let mut file = OpenOptions::new().append(true).open(path).unwrap();
+                file.write_all(b"1B ").unwrap();
+                file.flush().unwrap();
+                thread::sleep(Duration::from_millis(50));
+                file.write_all(b"HFjd=\n").unwrap();
+            })
+        };
+        // The line is only complete after the second write.
+        assert_eq!(lines.next().unwrap().unwrap(), "1B HFjd=");
+        writer.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn check_decompression() {
+        use std::io::Write;
+
+        let text = "1A G3Ss=\n1B HFjd=\n";
+        let read = |bytes: Vec<u8>| {
+            let mut out = String::new();
+            decompress(Box::new(io::Cursor::new(bytes)))
+                .unwrap()
+                .read_to_string(&mut out)
+                .unwrap();
+            out
+        };
+
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(text.as_bytes()).unwrap();
+        assert_eq!(read(gzip.finish().unwrap()), text);
+
+        assert_eq!(read(zstd::encode_all(text.as_bytes(), 0).unwrap()), text);
+        assert_eq!(read(text.as_bytes().to_vec()), text);
+
+        // A mapped file hands out all of it as one buffer.
+        let path = std::env::temp_dir().join("cwins-check-map-test.txt");
+        std::fs::write(&path, text).unwrap();
+        let mut map = map_file(&path).unwrap();
+        assert_eq!(map.fill_buf().unwrap(), text.as_bytes());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,25 @@
+// Stopping a long run cleanly on Ctrl-C (SIGINT) or SIGTERM: the handler only raises
+// a flag, the verification loop checks it between lines, reports what it has and
+// prints the summary. A second signal ends the process right away.
+
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The exit code of an interrupted run, 128 + SIGINT as shells report it.
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs the handler of both signals.
+pub fn install() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            process::exit(EXIT_INTERRUPTED.into());
+        }
+    })
+}
+
+/// Whether a signal asked the run to stop.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
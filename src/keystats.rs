@@ -0,0 +1,66 @@
+use bitcoin::secp256k1;
+use bitcoin::PublicKey;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Tracks which addresses every recovered public key signed for (`--key-stats`).
+#[derive(Debug, Default)]
+pub struct KeyStats {
+    signatures: usize,
+    addresses: BTreeMap<secp256k1::PublicKey, BTreeSet<String>>,
+}
+
+impl KeyStats {
+    pub fn add(&mut self, pubkey: &PublicKey, address: &str) {
+        self.signatures += 1;
+        self.addresses
+            .entry(pubkey.inner)
+            .or_default()
+            .insert(address.to_string());
+    }
+
+    pub fn signatures(&self) -> usize {
+        self.signatures
+    }
+
+    pub fn unique_keys(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Keys that signed for more than one distinct address.
+    pub fn shared_keys(&self) -> impl Iterator<Item = (&secp256k1::PublicKey, &BTreeSet<String>)> {
+        self.addresses.iter().filter(|(_, a)| a.len() > 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::secp256k1::Secp256k1;
+
+    #[test]
+    fn check_key_stats() {
+        let secp = Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let compressed = PublicKey::new(sk.public_key(&secp));
+        let uncompressed = PublicKey::new_uncompressed(sk.public_key(&secp));
+        let other = PublicKey::new(
+            secp256k1::SecretKey::from_slice(&[2u8; 32])
+                .unwrap()
+                .public_key(&secp),
+        );
+
+        let mut stats = KeyStats::default();
+        stats.add(&compressed, "1A");
+        stats.add(&compressed, "1A");
+        stats.add(&uncompressed, "1B");
+        stats.add(&other, "1C");
+
+        assert_eq!(stats.signatures(), 4);
+        assert_eq!(stats.unique_keys(), 2);
+
+        let shared: Vec<_> = stats.shared_keys().collect();
+        assert_eq!(shared.len(), 1);
+        assert_eq!(*shared[0].0, compressed.inner);
+        assert_eq!(shared[0].1.iter().collect::<Vec<_>>(), vec!["1A", "1B"]);
+    }
+}
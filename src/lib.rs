@@ -0,0 +1,698 @@
+// Code to check the signatures from [0] are valid.
+//
+// ---
+// Author: seberm
+//
+// Refs.:
+// - [0] https://craigwrightisnotsatoshi.com/
+// - [1] https://en.bitcoin.it/wiki/BIP_0137
+// - [2] https://github.com/bitcoin/bips/blob/master/bip-0322.mediawiki
+// - [3] https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+
+use bitcoin::address::{NetworkChecked, NetworkUnchecked};
+use bitcoin::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use bitcoin::sign_message::{MessageSignature, MessageSignatureError};
+use bitcoin::{Address, AddressType, Network, PublicKey};
+use std::error::Error;
+
+pub mod attest;
+#[cfg(feature = "balances")]
+pub mod balance;
+pub mod bench;
+pub mod bip322;
+pub mod cache;
+pub mod chain;
+pub mod compare;
+pub mod compat;
+pub mod config;
+pub mod descriptor;
+pub mod early;
+#[cfg(feature = "electrum")]
+pub mod electrum;
+pub mod ethereum;
+pub mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod filter;
+pub mod git;
+pub mod gpg;
+pub mod html;
+#[cfg(feature = "server")]
+pub mod http;
+#[cfg(feature = "hwi")]
+pub mod hwi;
+pub mod input;
+pub mod interrupt;
+pub mod keystats;
+pub mod lightning;
+pub mod logging;
+pub mod lookup;
+pub mod manifest;
+pub mod matcher;
+pub mod messages;
+#[cfg(feature = "server")]
+pub mod metrics;
+pub mod mnemonic;
+#[cfg(feature = "node")]
+pub mod node;
+pub mod normalize;
+#[cfg(feature = "ots")]
+pub mod ots;
+pub mod output;
+pub mod pipeline;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "repl")]
+pub mod repl;
+pub mod resume;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod schnorr;
+pub mod sign;
+pub mod signature;
+#[cfg(feature = "server")]
+pub mod socket;
+pub mod stats;
+#[cfg(feature = "sqlite")]
+pub mod store;
+pub mod testvectors;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod verifier;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod website;
+pub mod xpub;
+
+use compat::Compat;
+pub use matcher::{AddressMatcher, StandardMatcher};
+use signature::{
+    decode_signature_into, parse_signature_bytes, recover_pubkey, secp, signed_msg_hash,
+    SIGNATURE_LEN,
+};
+pub use verifier::{VerificationResult, Verifier};
+
+/// Why a signature couldn't be checked at all. A signature by a different key is
+/// not an error, verification just returns `Ok(false)`.
+///
+/// The underlying error, where there is one, is the [`Error::source`]; use
+/// [`error_reason`] for the whole chain.
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("cannot parse the address")]
+    AddressParseError(#[from] bitcoin::address::Error),
+    #[error("cannot decode the signature from base64")]
+    SignatureBase64DecodeError(#[from] base64::DecodeError),
+    #[error("cannot decode the signature from zbase32")]
+    SignatureZBase32DecodeError,
+    #[error("invalid signature header byte {0}, expected 27-42")]
+    InvalidHeader(u8),
+    #[error("signature header byte {0} doesn't match the address type")]
+    HeaderMismatch(u8),
+    #[error("cannot recover the public key")]
+    GeneralSignatureProblem(#[from] MessageSignatureError),
+    #[error("invalid BIP-322 proof: {0}")]
+    InvalidBip322Proof(&'static str),
+    #[error("the signature has a high s value, not the canonical low one")]
+    NonCanonical,
+}
+
+impl VerifyError {
+    /// Stable machine-readable name of the error, used by the JSON outputs.
+    pub fn code(&self) -> &'static str {
+        match self {
+            VerifyError::AddressParseError(_) => "address_parse",
+            VerifyError::SignatureBase64DecodeError(_) => "signature_base64",
+            VerifyError::SignatureZBase32DecodeError => "signature_zbase32",
+            VerifyError::InvalidHeader(_) => "invalid_header",
+            VerifyError::HeaderMismatch(_) => "header_mismatch",
+            VerifyError::GeneralSignatureProblem(_) => "signature",
+            VerifyError::InvalidBip322Proof(_) => "invalid_bip322_proof",
+            VerifyError::NonCanonical => "non_canonical",
+        }
+    }
+}
+
+/// The error message followed by the messages of all its sources, e.g.
+/// `invalid encoding: malformed signature`.
+pub fn error_reason(error: &dyn Error) -> String {
+    let mut reason = error.to_string();
+    let mut source = error.source();
+
+    while let Some(e) = source {
+        reason.push_str(": ");
+        reason.push_str(&e.to_string());
+        source = e.source();
+    }
+
+    reason
+}
+
+/// Version of the JSON objects (`--output-format json|ndjson`, the HTTP server) and
+/// the CSV rows, bumped whenever a field is removed or changes meaning.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The statement signed by the addresses listed on craigwrightisnotsatoshi.com.
+pub const MESSAGE: &str =
+"Craig Steven Wright is a liar and a fraud. He doesn't have the keys used to sign this message.
+
+The Lightning Network is a significant achievement. However, we need to continue work on improving on-chain capacity.
+
+Unfortunately, the solution is not to just change a constant in the code or to allow powerful participants to force out others.
+
+We are all Satoshi";
+
+/// Verifies `signature` of `message` against one of the standard address types.
+///
+/// 65 byte signatures are BIP-137 [1] ones, 64 byte ones for P2TR addresses are
+/// BIP-340 [3] signatures of the same message hash by the output key, anything else is
+/// taken as a BIP-322 [2] simple or full proof.
+///
+/// Use a [`Verifier`] for many signatures of the same message.
+pub fn check_sig(
+    address: Address<NetworkChecked>,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    Verifier::new(message).verify_one(&address, signature)
+}
+
+/// Like [`check_sig`], with a custom rule deciding whether the recovered key matches.
+pub fn check_sig_with(
+    matcher: &impl AddressMatcher,
+    address: Address<NetworkChecked>,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    let pubkey = recover_pubkey(message, signature)?;
+
+    Ok(matcher.matches(&pubkey, &address))
+}
+
+/// Verifies a BIP-137 `signature` of `message` by `pubkey` itself, without deriving
+/// any address. The compression flag of the signature header doesn't matter.
+pub fn check_sig_by_pubkey(
+    pubkey: &PublicKey,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    Ok(recover_pubkey(message, signature)?.inner == pubkey.inner)
+}
+
+/// Retries a 65 byte `signature` that doesn't verify by `address` with all four
+/// recovery ids and both compression flags, ignoring its header byte. Returns the
+/// BIP-137 header of the combination that matches, if any.
+pub fn check_sig_lenient(
+    address: &Address<NetworkChecked>,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<Option<u8>, VerifyError> {
+    let mut buf = [0; SIGNATURE_LEN];
+    let bytes = decode_signature_into(signature, &mut buf)?;
+    if bytes.len() != SIGNATURE_LEN {
+        return Err(MessageSignatureError::InvalidLength.into());
+    }
+
+    let secp = secp();
+    let msg_hash = signed_msg_hash(message);
+    for recid in 0..4 {
+        let id = RecoveryId::from_i32(recid).map_err(MessageSignatureError::from)?;
+        let sig = RecoverableSignature::from_compact(&bytes[1..], id)
+            .map_err(MessageSignatureError::from)?;
+
+        for compressed in [true, false] {
+            let pubkey = match MessageSignature::new(sig, compressed).recover_pubkey(secp, msg_hash)
+            {
+                Ok(pubkey) => pubkey,
+                // Not every recovery id yields a point.
+                Err(_) => continue,
+            };
+            if StandardMatcher.matches(&pubkey, address) {
+                let base = match (address.address_type(), compressed) {
+                    (Some(AddressType::P2sh), _) => 35,
+                    (Some(AddressType::P2wpkh), _) => 39,
+                    (_, true) => 31,
+                    (_, false) => 27,
+                };
+                return Ok(Some(base + recid as u8));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Fails with [`VerifyError::HeaderMismatch`] if a 65 byte `signature` is by the key
+/// of `address` but its header isn't the one BIP-137 prescribes for the address type:
+/// the key compression of P2PKH addresses, 35-38 for P2SH-P2WPKH and 39-42 for
+/// P2WPKH. Other signatures pass.
+pub fn check_header(
+    address: &Address<NetworkChecked>,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<(), VerifyError> {
+    let mut buf = [0; SIGNATURE_LEN];
+    let bytes = decode_signature_into(signature, &mut buf)?;
+    if bytes.len() != SIGNATURE_LEN {
+        return Ok(());
+    }
+
+    let pubkey = recover_pubkey(message, signature)?;
+    let flipped = PublicKey {
+        inner: pubkey.inner,
+        compressed: !pubkey.compressed,
+    };
+    let consistent = if StandardMatcher.matches(&pubkey, address) {
+        Compat::Strict.allows_header(bytes[0], address.address_type())
+    } else {
+        !StandardMatcher.matches(&flipped, address)
+    };
+
+    if consistent {
+        Ok(())
+    } else {
+        Err(VerifyError::HeaderMismatch(bytes[0]))
+    }
+}
+
+/// Fails with [`VerifyError::NonCanonical`] if a 65 byte `signature` has a high `s`
+/// (`--require-low-s`): its twin with `n - s` verifies just the same, so the
+/// encoding is malleable. Other signatures pass.
+pub fn check_low_s(signature: &str) -> Result<(), VerifyError> {
+    match signature::decompose(signature) {
+        Ok(parts) if !parts.low_s => Err(VerifyError::NonCanonical),
+        _ => Ok(()),
+    }
+}
+
+/// Checks that `signature` is well-formed for `address` without verifying it (the
+/// `--check-format` pre-flight): a 65 byte BIP-137 signature with a header in range
+/// and a valid `r || s`, a 64 byte BIP-340 one for a P2TR address or a decodable
+/// BIP-322 proof.
+pub fn check_format(address: &Address<NetworkChecked>, signature: &str) -> Result<(), VerifyError> {
+    let mut buf = [0; SIGNATURE_LEN];
+    let bytes = decode_signature_into(signature, &mut buf)?;
+    match bytes.len() {
+        SIGNATURE_LEN => parse_signature_bytes(&bytes).map(|_| ()),
+        64 if schnorr::taproot_output_key(address).is_some() => {
+            bitcoin::secp256k1::schnorr::Signature::from_slice(&bytes)
+                .map(|_| ())
+                .map_err(|e| MessageSignatureError::InvalidEncoding(e).into())
+        }
+        _ => bip322::decode_proof(&bytes).map(|_| ()),
+    }
+}
+
+/// Verifies a base64 BIP-137 or BIP-322 `signature` of `message` by the mainnet `address`.
+///
+/// Returns `Ok(false)` for a well-formed signature made by a different key.
+pub fn verify_message(address: &str, message: &str, signature: &str) -> Result<bool, VerifyError> {
+    verify_message_on(Network::Bitcoin, address, message, signature)
+}
+
+/// Like [`verify_message`], for an address of another (e.g. test) network.
+pub fn verify_message_on(
+    network: Network,
+    address: &str,
+    message: &str,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    let address: Address<NetworkChecked> = address
+        .parse::<Address<NetworkUnchecked>>()?
+        .require_network(network)?;
+
+    check_sig(address, message, signature)
+}
+
+/// Verifies a `signature` of `message` by the mainnet `address`, for tests and scripts.
+///
+/// # Panics
+///
+/// Unless the signature is a valid one by the address, with the address and why in
+/// the message, e.g. `1FbPLPR...: signature does not verify: signed by another key`.
+pub fn assert_signed(address: &str, signature: &str, message: &str) {
+    match verify_message(address, message, signature) {
+        Ok(true) => {}
+        Ok(false) => panic!(
+            "{}: signature does not verify: signed by another key",
+            address
+        ),
+        Err(e) => panic!(
+            "{}: signature does not verify: {}",
+            address,
+            error_reason(&e)
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use bitcoin::secp256k1;
+    use bitcoin::sign_message::signed_msg_hash;
+    use bitcoin::PublicKey;
+    use signature::decode_signature;
+
+    #[test]
+    fn check_valid_signatures() {
+        let checks: Vec<(&str, &str)> = vec![
+            ("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI="),
+            ("19PYG68GkQ9nY99QeUSyUFy6vWxSyPmXA8", "HFjd/SzCNDyXRY/skSjEKusK/adVtBf0ldT1ayvPb+WsLa5Qr0A4seEXjOmtg9K/wcJnv/E3F5TezZNB/ULoZI8="),
+            ("12cFuwo1i3FMhkmJoCN8D4SjeCeRsXf96q", "GySQXGlZ+Meq3braDzg3lq7GStteOg+0A9Q5gGKzCcOmET5vnULXo0vsb6anu1wLSL1BnaD0p71U9i+c41Fq48w="),
+            ("1NWRrbPwHhpp28eQeman5YRV84D2aYe1Yw", "HDE35UqJUUa8tkjt3NThu+SwF8arV27Lwg6idBTN7lm+epmjdQlvnWvCqUHrOBPCPQ50aK5VhLnUUFIEDE4KXlo="),
+            ("1MN82eH1Eu3hznewHFkfsAajknhj78Uup5", "HAZ+ot0bWlK4t40kTqC9H0tCjVeCa3WCR0xyYNMX94uqAAXTOHITT8X0QzQI4UFlHCzPhfcxsgMgniiTY0FkUHc="),
+            ("1DYHUEjrVE5gyKAn7P13wuRhs6x9EeijBX", "G08ZpNNnXNawyvIEpa79QpP4+MjZhBd1+0/nAGCcI5X2DgtqfJDyYVpkVg9VXXy9rG7B/NK8TmdO4ep62QLkvlw="),
+            ("1KnT26DTvstGKW7P6BxMBEz8QbKa1iix9C", "HF4BP/4DlRRJ38MlS0zcI9MDNWAfDZo3apmD+wzPPMfdAfuzt0ae0OOrUNW6ye+6mPYSwmnOaUfhR2EqyivCpX4="),
+        ];
+
+        for (address, signature) in checks.iter() {
+            assert!(check_sig(
+                address
+                    .parse::<Address<_>>()
+                    .unwrap()
+                    .require_network(Network::Bitcoin)
+                    .unwrap(),
+                MESSAGE,
+                signature
+            )
+            .is_ok());
+        }
+    }
+
+    #[test]
+    fn check_invalid_signatures() {
+        let checks: Vec<(&str, &str)> = vec![
+            ("19PYG68GkQ9nY99QeUSyUFy6vWxSyPmXA8", "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI="),
+            ("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", "HFjd/SzCNDyXRY/skSjEKusK/adVtBf0ldT1ayvPb+WsLa5Qr0A4seEXjOmtg9K/wcJnv/E3F5TezZNB/ULoZI8="),
+        ];
+
+        for (address, signature) in checks.iter() {
+            let r: Result<bool, VerifyError> = check_sig(
+                address
+                    .parse::<Address<_>>()
+                    .unwrap()
+                    .require_network(Network::Bitcoin)
+                    .unwrap(),
+                MESSAGE,
+                signature,
+            );
+            assert!(r.is_ok());
+            assert_eq!(r.ok(), Some(false));
+        }
+    }
+
+    #[test]
+    fn check_verify_message() {
+        assert_eq!(
+            verify_message(
+                "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m",
+                MESSAGE,
+                "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI="
+            )
+            .ok(),
+            Some(true)
+        );
+        assert_eq!(
+            verify_message(
+                "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m",
+                "We are all Satoshi",
+                "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI="
+            )
+            .ok(),
+            Some(false)
+        );
+        assert!(matches!(
+            verify_message("1FbPLPR1XoufBQRaGJ9JBLPbKLaGjbax5c", MESSAGE, ""),
+            Err(VerifyError::AddressParseError(_))
+        ));
+        assert!(matches!(
+            verify_message("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", MESSAGE, ""),
+            Err(VerifyError::AddressParseError(_))
+        ));
+    }
+
+    #[test]
+    fn check_assert_signed() {
+        assert_signed(
+            "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m",
+            "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=",
+            MESSAGE,
+        );
+
+        let message = |address: &'static str, signature: &'static str| {
+            let panic = std::panic::catch_unwind(|| assert_signed(address, signature, MESSAGE))
+                .expect_err("assert_signed didn't panic");
+            *panic.downcast::<String>().unwrap()
+        };
+        assert_eq!(
+            message(
+                "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m",
+                "HFjd/SzCNDyXRY/skSjEKusK/adVtBf0ldT1ayvPb+WsLa5Qr0A4seEXjOmtg9K/wcJnv/E3F5TezZNB/ULoZI8="
+            ),
+            "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m: signature does not verify: signed by another key"
+        );
+        assert!(message("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", "not base64")
+            .starts_with("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m: signature does not verify: cannot decode the signature from base64"));
+        assert!(message("1Fb", "")
+            .starts_with("1Fb: signature does not verify: cannot parse the address"));
+    }
+
+    #[test]
+    fn check_sig_by_pubkey_directly() {
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+        let uncompressed: PublicKey = "04e5d980b2ec08c9e24f354e70bde2d60c8d7c33041bc88f0ac11555feae642554050f14e640a78c115f3b67022c374a910dce06caedd09e9496a0f6cff26f1fbf".parse().unwrap();
+        let compressed = PublicKey::new(uncompressed.inner);
+
+        assert_eq!(
+            check_sig_by_pubkey(&uncompressed, MESSAGE, signature).ok(),
+            Some(true)
+        );
+        assert_eq!(
+            check_sig_by_pubkey(&compressed, MESSAGE, signature).ok(),
+            Some(true)
+        );
+        assert_eq!(
+            check_sig_by_pubkey(&compressed, "other", signature).ok(),
+            Some(false)
+        );
+        assert!(check_sig_by_pubkey(&compressed, MESSAGE, "bad").is_err());
+    }
+
+    #[test]
+    fn check_lenient_recovery() {
+        use base64::Engine;
+
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let mut bytes = decode_signature("G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=").unwrap();
+
+        // A compressed key and the wrong recovery id, then a header out of range.
+        for header in [32, 200] {
+            bytes[0] = header;
+            let signature = base64::engine::general_purpose::STANDARD.encode(&bytes);
+            assert_ne!(
+                check_sig(address.clone(), MESSAGE, &signature).ok(),
+                Some(true)
+            );
+            assert_eq!(
+                check_sig_lenient(&address, MESSAGE, &signature).ok(),
+                Some(Some(27))
+            );
+            assert_eq!(
+                check_sig_lenient(&address, "other", &signature).ok(),
+                Some(None)
+            );
+        }
+        assert!(check_sig_lenient(&address, MESSAGE, "bad").is_err());
+    }
+
+    #[test]
+    fn check_header_consistency() {
+        use base64::Engine;
+
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+        assert!(check_header(&address, MESSAGE, signature).is_ok());
+        assert!(check_header(&address, "other", signature).is_ok());
+
+        // The compressed flag on a signature for an uncompressed key's address.
+        let mut bytes = decode_signature(signature).unwrap();
+        bytes[0] = 31;
+        let compressed = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        assert!(matches!(
+            check_header(&address, MESSAGE, &compressed),
+            Err(VerifyError::HeaderMismatch(31))
+        ));
+
+        let secp = secp256k1::Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_slice(&[9u8; 32]).unwrap();
+        let pubkey = PublicKey::new(sk.public_key(&secp));
+        let segwit = Address::p2wpkh(&pubkey, Network::Bitcoin).unwrap();
+        assert!(check_header(&segwit, MESSAGE, &sign(&sk, MESSAGE, 39)).is_ok());
+        assert!(matches!(
+            check_header(&segwit, MESSAGE, &sign(&sk, MESSAGE, 31)),
+            Err(VerifyError::HeaderMismatch(h)) if (31..=34).contains(&h)
+        ));
+    }
+
+    #[test]
+    fn check_low_s_signatures() {
+        use base64::Engine;
+
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+        assert!(check_low_s(signature).is_ok());
+
+        // s replaced by n - s (and recid 1 for the negated R) verifies just the same.
+        let mut bytes = decode_signature(signature).unwrap();
+        let high = secp256k1::SecretKey::from_slice(&bytes[33..])
+            .unwrap()
+            .negate();
+        bytes[33..].copy_from_slice(&high.secret_bytes());
+        bytes[0] = 28;
+        let malleated = base64::engine::general_purpose::STANDARD.encode(&bytes);
+        assert!(verify_message("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", MESSAGE, &malleated).unwrap());
+        assert!(matches!(
+            check_low_s(&malleated),
+            Err(VerifyError::NonCanonical)
+        ));
+
+        // Not a BIP-137 signature, nothing to check.
+        assert!(check_low_s(&"ab".repeat(64)).is_ok());
+    }
+
+    #[test]
+    fn check_test_networks() {
+        let secp = secp256k1::Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_slice(&[9u8; 32]).unwrap();
+        let pubkey = PublicKey::new(sk.public_key(&secp));
+        let signature = sign(&sk, MESSAGE, 39);
+
+        for network in [Network::Testnet, Network::Signet, Network::Regtest] {
+            let address = Address::p2wpkh(&pubkey, network).unwrap().to_string();
+
+            assert_eq!(
+                verify_message_on(network, &address, MESSAGE, &signature).ok(),
+                Some(true)
+            );
+            assert!(matches!(
+                verify_message(&address, MESSAGE, &signature),
+                Err(VerifyError::AddressParseError(_))
+            ));
+        }
+
+        // Testnet and signet share the address prefixes.
+        let address = Address::p2pkh(&pubkey, Network::Testnet).to_string();
+        assert_eq!(
+            verify_message_on(Network::Signet, &address, MESSAGE, &sign(&sk, MESSAGE, 31)).ok(),
+            Some(true)
+        );
+    }
+
+    // BIP-137 signature with the header offset `base` (27, 31, 35 or 39).
+    fn sign(secret_key: &secp256k1::SecretKey, message: &str, base: u8) -> String {
+        let secp = secp256k1::Secp256k1::new();
+        let msg = secp256k1::Message::from_slice(&signed_msg_hash(message)[..]).unwrap();
+        let (recid, compact) = secp
+            .sign_ecdsa_recoverable(&msg, secret_key)
+            .serialize_compact();
+
+        let mut bytes = vec![base + recid.to_i32() as u8];
+        bytes.extend_from_slice(&compact);
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    #[test]
+    fn check_segwit_signatures() {
+        let secp = secp256k1::Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let pubkey = PublicKey::new(sk.public_key(&secp));
+
+        let p2wpkh = Address::p2wpkh(&pubkey, Network::Bitcoin).unwrap();
+        let p2shwpkh = Address::p2shwpkh(&pubkey, Network::Bitcoin).unwrap();
+
+        // The segwit headers, and the compressed P2PKH one some wallets (e.g.
+        // Electrum) use for every address type.
+        for (address, base) in [
+            (&p2wpkh, 39),
+            (&p2shwpkh, 35),
+            (&p2wpkh, 31),
+            (&p2shwpkh, 31),
+        ] {
+            let signature = sign(&sk, MESSAGE, base);
+
+            assert_eq!(
+                verify_message(&address.to_string(), MESSAGE, &signature).ok(),
+                Some(true)
+            );
+            assert_eq!(
+                verify_message(&address.to_string(), "We are all Satoshi", &signature).ok(),
+                Some(false)
+            );
+        }
+
+        // An uncompressed key has no segwit address.
+        let signature = sign(&sk, MESSAGE, 27);
+        assert_eq!(
+            verify_message(&p2wpkh.to_string(), MESSAGE, &signature).ok(),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn check_invalid_addresses() {
+        let checks: Vec<&str> = vec![
+            "19PYG68GkQ9nY99aEJSyUFy6vWxSyPmXAa",
+            "1FbPLPR1XoufBQRaGJ9JBLPbKLaGjbax5c",
+        ];
+
+        for address in checks.iter() {
+            assert!(address.parse::<Address<_>>().is_err());
+        }
+    }
+
+    #[test]
+    fn check_error_chain() {
+        let error = verify_message("1Fb", MESSAGE, "G3Ss").unwrap_err();
+        assert_eq!(error.to_string(), "cannot parse the address");
+        assert!(error.source().is_some());
+
+        let error =
+            verify_message("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", MESSAGE, "G3S!").unwrap_err();
+        assert!(matches!(error, VerifyError::SignatureBase64DecodeError(_)));
+        assert_eq!(
+            error_reason(&error),
+            "cannot decode the signature from base64: Invalid symbol 33, offset 3."
+        );
+    }
+
+    #[test]
+    fn check_signature_format() {
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        // Well-formed, whatever the message it would verify for.
+        let signature = "HFjd/SzCNDyXRY/skSjEKusK/adVtBf0ldT1ayvPb+WsLa5Qr0A4seEXjOmtg9K/wcJnv/E3F5TezZNB/ULoZI8=";
+        assert!(check_format(&address, signature).is_ok());
+
+        let header = format!("2b{}", "11".repeat(64));
+        assert!(matches!(
+            check_format(&address, &header),
+            Err(VerifyError::InvalidHeader(43))
+        ));
+        assert!(matches!(
+            check_format(&address, "G3S!"),
+            Err(VerifyError::SignatureBase64DecodeError(_))
+        ));
+        assert!(check_format(&address, "AAAA").is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::signature::{parse_signature_bytes, secp};
+use crate::VerifyError;
+use bitcoin::hashes::{sha256d, Hash, HashEngine};
+use bitcoin::secp256k1;
+
+pub const LIGHTNING_SIGNED_MSG_PREFIX: &[u8] = b"Lightning Signed Message:";
+
+pub const ZBASE32_ALPHABET: &[u8; 32] = b"ybndrfg8ejkmcpqxot1uwisza345h769";
+
+pub fn zbase32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+
+    for c in s.bytes() {
+        let value = ZBASE32_ALPHABET.iter().position(|&a| a == c)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(out)
+}
+
+/// Hash used by lnd/CLN `signmessage`: double SHA-256 of the prefix and the message
+/// (no length varint, unlike BIP-137).
+pub fn lightning_msg_hash(message: impl AsRef<[u8]>) -> sha256d::Hash {
+    let mut engine = sha256d::Hash::engine();
+    engine.input(LIGHTNING_SIGNED_MSG_PREFIX);
+    engine.input(message.as_ref());
+    sha256d::Hash::from_engine(engine)
+}
+
+/// Whether `s` looks like a node id (a hex compressed public key) rather than an
+/// address, used to tell Lightning lines apart in a mixed list.
+pub fn is_node_id(s: &str) -> bool {
+    s.len() == 66
+        && (s.starts_with("02") || s.starts_with("03"))
+        && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Verifies a zbase32 Lightning signed message against a node public key.
+pub fn check_lightning_sig(
+    node_id: &secp256k1::PublicKey,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    let secp = secp();
+
+    let bytes = zbase32_decode(signature).ok_or(VerifyError::SignatureZBase32DecodeError)?;
+    let sss = parse_signature_bytes(&bytes)?;
+
+    let pubkey = sss.recover_pubkey(secp, lightning_msg_hash(message))?;
+
+    Ok(pubkey.inner == *node_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_lightning_signatures() {
+        // From the Core Lightning `checkmessage` test corpus.
+        let node_id = "02b80cabdf82638aac86948e4c06e82064f547768dcef977677b9ea931ea75bab5"
+            .parse::<secp256k1::PublicKey>()
+            .unwrap();
+        let signature = "rbgfioj114mh48d8egqx8o9qxqw4fmhe8jbeeabdioxnjk8z3t1ma1hu1fiswpakgucwwzwo6ofycffbsqusqdimugbh41n1g698hr9t";
+
+        assert_eq!(
+            check_lightning_sig(&node_id, "is this compatible?", signature).ok(),
+            Some(true)
+        );
+        assert_eq!(
+            check_lightning_sig(&node_id, "is this compatible", signature).ok(),
+            Some(false)
+        );
+        assert!(matches!(
+            check_lightning_sig(&node_id, "is this compatible?", "not zbase32!"),
+            Err(VerifyError::SignatureZBase32DecodeError)
+        ));
+
+        assert!(is_node_id(
+            "02b80cabdf82638aac86948e4c06e82064f547768dcef977677b9ea931ea75bab5"
+        ));
+        assert!(!is_node_id("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"));
+        assert!(!is_node_id(
+            "04b80cabdf82638aac86948e4c06e82064f547768dcef977677b9ea931ea75bab5"
+        ));
+    }
+}
@@ -0,0 +1,41 @@
+// Log records on stderr through `tracing`: readable lines or, with `--log-format
+// json`, one JSON object per record with its fields and spans (the input file and
+// line being verified) for log pipelines. `RUST_LOG` picks the levels, errors only
+// by default.
+
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing_subscriber::filter::{filter_fn, EnvFilter};
+use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
+
+static MUTED: AtomicBool = AtomicBool::new(false);
+
+/// Stops or resumes all logging, e.g. while the dashboard owns the terminal.
+pub fn mute(muted: bool) {
+    MUTED.store(muted, Ordering::Relaxed);
+}
+
+/// Installs the global subscriber, `off` for no logging at all (`--quiet`).
+pub fn init(json: bool, off: bool) {
+    let filter = match off {
+        true => EnvFilter::new("off"),
+        false => EnvFilter::from_default_env(),
+    };
+    let unmuted = || filter_fn(|_| !MUTED.load(Ordering::Relaxed));
+    let registry = tracing_subscriber::registry().with(filter);
+
+    if json {
+        let layer = fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(io::stderr);
+        registry.with(layer.with_filter(unmuted())).init();
+    } else {
+        let layer = fmt::layer()
+            .with_ansi(io::stderr().is_terminal())
+            .with_writer(io::stderr);
+        registry.with(layer.with_filter(unmuted())).init();
+    }
+}
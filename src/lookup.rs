@@ -0,0 +1,354 @@
+// Network lookups for the verified lines (Esplora balances, Bitcoin Core
+// cross-checks). They are collected over a batch and run together: one after the
+// other, or with the `async` feature on a tokio runtime with at most `jobs` of them
+// in flight. The clients themselves stay blocking, each lookup runs on tokio's
+// blocking pool.
+//
+// Public servers are spared by a rate limit shared by all the lookups, retries with
+// an exponential backoff and an on-disk cache of the answers by address, so a
+// re-run of a long list only asks for what it doesn't know yet.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Runs the lookups of a batch.
+pub struct Lookups {
+    #[cfg(feature = "async")]
+    concurrent: Option<(tokio::runtime::Runtime, usize)>,
+}
+
+impl Lookups {
+    /// Lookups run one after the other.
+    pub fn serial() -> Self {
+        Lookups {
+            #[cfg(feature = "async")]
+            concurrent: None,
+        }
+    }
+
+    /// Lookups run with at most `jobs` of them at a time.
+    #[cfg(feature = "async")]
+    pub fn concurrent(jobs: usize) -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .max_blocking_threads(jobs.max(1))
+            .build()?;
+        Ok(Lookups {
+            concurrent: Some((runtime, jobs.max(1))),
+        })
+    }
+
+    /// `lookup` of every item with the shared `client`, the results in the order of
+    /// `items`.
+    pub fn run<C, T, R>(&self, client: &Arc<C>, items: Vec<T>, lookup: fn(&C, T) -> R) -> Vec<R>
+    where
+        C: Send + Sync + 'static,
+        T: Send + 'static,
+        R: Send + 'static,
+    {
+        #[cfg(feature = "async")]
+        if let Some((runtime, jobs)) = &self.concurrent {
+            return run_concurrent(runtime, *jobs, client, items, lookup);
+        }
+
+        items.into_iter().map(|item| lookup(client, item)).collect()
+    }
+}
+
+/// Spaces out the requests to a server: at most `per_second` of them start each
+/// second, however many lookups run at once.
+#[derive(Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: f64) -> Self {
+        let interval = match per_second {
+            rate if rate > 0.0 => Duration::from_secs_f64(1.0 / rate),
+            _ => Duration::ZERO,
+        };
+        RateLimiter {
+            interval,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Requests as fast as they come.
+    pub fn unlimited() -> Self {
+        Self::new(0.0)
+    }
+
+    /// Waits for the next free slot.
+    pub fn wait(&self) {
+        let now = Instant::now();
+        let start = {
+            let mut next = self.next.lock().expect("rate limiter lock");
+            let start = (*next).max(now);
+            *next = start + self.interval;
+            start
+        };
+        thread::sleep(start - now);
+    }
+}
+
+/// A client whose requests go through a [`RateLimiter`], failed ones are retried
+/// up to `retries` times after 1 s, 2 s, 4 s, ...
+#[derive(Debug)]
+pub struct Throttled<C> {
+    pub client: C,
+    limiter: RateLimiter,
+    retries: u32,
+    backoff: Duration,
+}
+
+impl<C> Throttled<C> {
+    pub fn new(client: C, limiter: RateLimiter, retries: u32) -> Self {
+        Throttled {
+            client,
+            limiter,
+            retries,
+            backoff: Duration::from_secs(1),
+        }
+    }
+
+    /// The first wait between retries, doubled after each one.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// `request` with the client, retried while it fails.
+    pub fn call<R, E: fmt::Display>(
+        &self,
+        mut request: impl FnMut(&C) -> Result<R, E>,
+    ) -> Result<R, E> {
+        retry(&self.limiter, self.retries, self.backoff, || {
+            request(&self.client)
+        })
+    }
+
+    /// [`Throttled::call`] for clients needing a mutable reference.
+    pub fn call_mut<R, E: fmt::Display>(
+        &mut self,
+        mut request: impl FnMut(&mut C) -> Result<R, E>,
+    ) -> Result<R, E> {
+        let client = &mut self.client;
+        retry(&self.limiter, self.retries, self.backoff, || {
+            request(client)
+        })
+    }
+
+    /// `request` with the client, only spaced out: for answers that don't change
+    /// when asked again.
+    pub fn paced<R>(&self, request: impl FnOnce(&C) -> R) -> R {
+        self.limiter.wait();
+        request(&self.client)
+    }
+}
+
+fn retry<R, E: fmt::Display>(
+    limiter: &RateLimiter,
+    retries: u32,
+    mut backoff: Duration,
+    mut attempt: impl FnMut() -> Result<R, E>,
+) -> Result<R, E> {
+    for _ in 0..retries {
+        limiter.wait();
+        match attempt() {
+            Ok(answer) => return Ok(answer),
+            Err(e) => warn!("Lookup failed, retrying in {:?}: {}", backoff, e),
+        }
+        thread::sleep(backoff);
+        backoff *= 2;
+    }
+    limiter.wait();
+    attempt()
+}
+
+/// One answer in a [`LookupCache`].
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Cached {
+    /// Unix time of the lookup.
+    fetched: u64,
+    value: Value,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Answers of earlier lookups by kind (`balance`, `history`) and address, persisted
+/// between runs with `--lookup-cache`. Answers older than the TTL are asked again.
+#[derive(Debug, Default, PartialEq)]
+pub struct LookupCache {
+    entries: BTreeMap<String, Cached>,
+    ttl: Duration,
+}
+
+impl LookupCache {
+    pub fn new(ttl: Duration) -> Self {
+        LookupCache {
+            entries: BTreeMap::new(),
+            ttl,
+        }
+    }
+
+    pub fn load(path: &Path, ttl: Duration) -> Self {
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Self::new(ttl),
+            Err(e) => {
+                warn!("Ignoring unreadable lookup cache {}: {}", path.display(), e);
+                return Self::new(ttl);
+            }
+        };
+
+        match serde_json::from_str(&content) {
+            Ok(entries) => LookupCache { entries, ttl },
+            Err(_) => {
+                warn!("Ignoring corrupt lookup cache {}", path.display());
+                Self::new(ttl)
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(&self.entries)?)
+    }
+
+    /// The answer looked up for `address` within the TTL.
+    pub fn get(&self, kind: &str, address: &str) -> Option<&Value> {
+        let cached = self.entries.get(&format!("{}:{}", kind, address))?;
+        (unix_now().saturating_sub(cached.fetched) < self.ttl.as_secs()).then_some(&cached.value)
+    }
+
+    pub fn insert(&mut self, kind: &str, address: &str, value: Value) {
+        let cached = Cached {
+            fetched: unix_now(),
+            value,
+        };
+        self.entries.insert(format!("{}:{}", kind, address), cached);
+    }
+}
+
+#[cfg(feature = "async")]
+fn run_concurrent<C, T, R>(
+    runtime: &tokio::runtime::Runtime,
+    jobs: usize,
+    client: &Arc<C>,
+    items: Vec<T>,
+    lookup: fn(&C, T) -> R,
+) -> Vec<R>
+where
+    C: Send + Sync + 'static,
+    T: Send + 'static,
+    R: Send + 'static,
+{
+    let limit = Arc::new(tokio::sync::Semaphore::new(jobs));
+
+    runtime.block_on(async {
+        let tasks: Vec<_> = items
+            .into_iter()
+            .map(|item| {
+                let (client, limit) = (client.clone(), limit.clone());
+                tokio::spawn(async move {
+                    let _permit = limit.acquire_owned().await.expect("semaphore closed");
+                    tokio::task::spawn_blocking(move || lookup(&client, item)).await
+                })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(tasks.len());
+        for task in tasks {
+            match task.await {
+                Ok(Ok(result)) => results.push(result),
+                Ok(Err(e)) | Err(e) => std::panic::resume_unwind(e.into_panic()),
+            }
+        }
+        results
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    /// Counts the lookups in flight and the most seen at once.
+    #[derive(Default)]
+    struct Gauge {
+        current: AtomicUsize,
+        most: AtomicUsize,
+    }
+
+    fn slow_square(gauge: &Gauge, n: u64) -> u64 {
+        let current = gauge.current.fetch_add(1, Ordering::SeqCst) + 1;
+        gauge.most.fetch_max(current, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+        gauge.current.fetch_sub(1, Ordering::SeqCst);
+        n * n
+    }
+
+    #[test]
+    fn check_lookups() {
+        let gauge = Arc::new(Gauge::default());
+        let squares = Lookups::serial().run(&gauge, (1..=5).collect(), slow_square);
+        assert_eq!(squares, [1, 4, 9, 16, 25]);
+        assert_eq!(gauge.most.load(Ordering::SeqCst), 1);
+
+        #[cfg(feature = "async")]
+        {
+            let gauge = Arc::new(Gauge::default());
+            let lookups = Lookups::concurrent(3).unwrap();
+            let squares = lookups.run(&gauge, (1..=12).collect(), slow_square);
+            assert_eq!(squares, (1..=12).map(|n| n * n).collect::<Vec<_>>());
+            assert_eq!(gauge.most.load(Ordering::SeqCst), 3);
+        }
+    }
+
+    #[test]
+    fn check_throttled() {
+        let limiter = RateLimiter::new(100.0);
+        let started = Instant::now();
+        for _ in 0..5 {
+            limiter.wait();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(40));
+
+        // Fails twice, then answers.
+        let throttled = Throttled::new(AtomicUsize::new(0), RateLimiter::unlimited(), 3)
+            .backoff(Duration::from_millis(1));
+        let answer = throttled.call(|calls| match calls.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => Err("busy"),
+            n => Ok(n),
+        });
+        assert_eq!(answer, Ok(2));
+        let throttled = Throttled::new((), RateLimiter::unlimited(), 1).backoff(Duration::ZERO);
+        assert_eq!(throttled.call(|_| Err::<(), _>("down")), Err("down"));
+
+        let path = std::env::temp_dir().join("cwins-check-lookup-cache.json");
+        let mut cache = LookupCache::new(Duration::from_secs(60));
+        cache.insert("balance", "1A", 150.into());
+        assert_eq!(cache.get("balance", "1A"), Some(&Value::from(150)));
+        assert_eq!(cache.get("history", "1A"), None);
+        cache.save(&path).unwrap();
+        assert_eq!(LookupCache::load(&path, Duration::from_secs(60)), cache);
+        let expired = LookupCache::load(&path, Duration::ZERO);
+        assert_eq!(expired.get("balance", "1A"), None);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
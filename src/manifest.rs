@@ -0,0 +1,137 @@
+// Checking the input files against a `sha256sum` manifest (`--verify-manifest`)
+// before verifying them, so a run is tied to the exact published snapshot of the
+// list. The manifest has `<hex digest>  <file>` lines, `*<file>` for binary mode.
+
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestError {
+    #[error("line {0} of the manifest isn't `<sha256>  <file>`")]
+    Syntax(usize),
+    #[error("{0} isn't listed in the manifest")]
+    Missing(String),
+    #[error("{file} has the SHA-256 {actual}, the manifest lists {expected}")]
+    Mismatch {
+        file: String,
+        expected: sha256::Hash,
+        actual: sha256::Hash,
+    },
+    #[error("cannot hash {0}: {1}")]
+    Io(String, #[source] io::Error),
+}
+
+/// The digests of a manifest by the file names it lists.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    digests: HashMap<String, sha256::Hash>,
+}
+
+impl Manifest {
+    /// Parses `sha256sum` output, skipping blank lines and `#` comments.
+    pub fn parse(text: &str) -> Result<Self, ManifestError> {
+        let mut digests = HashMap::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (digest, file) = line
+                .split_once(' ')
+                .and_then(|(digest, rest)| Some((digest.parse().ok()?, rest)))
+                .ok_or(ManifestError::Syntax(n + 1))?;
+            let file = file
+                .strip_prefix(' ')
+                .or_else(|| file.strip_prefix('*'))
+                .ok_or(ManifestError::Syntax(n + 1))?;
+            digests.insert(file.to_string(), digest);
+        }
+
+        Ok(Manifest { digests })
+    }
+
+    /// The digest listed for `path`, by the path as given or by its file name.
+    fn expected(&self, path: &Path) -> Option<sha256::Hash> {
+        let name = path.file_name()?.to_string_lossy();
+        let given = path.to_string_lossy();
+        let given = given.strip_prefix("./").unwrap_or(&given);
+        self.digests
+            .get(given)
+            .or_else(|| self.digests.get(name.as_ref()))
+            .copied()
+    }
+
+    /// Hashes the file at `path` and returns its digest if it is the listed one.
+    pub fn check(&self, path: &Path) -> Result<sha256::Hash, ManifestError> {
+        let file = path.display().to_string();
+        let expected = self
+            .expected(path)
+            .ok_or_else(|| ManifestError::Missing(file.clone()))?;
+        let actual = file_digest(path).map_err(|e| ManifestError::Io(file.clone(), e))?;
+        if actual != expected {
+            return Err(ManifestError::Mismatch {
+                file,
+                expected,
+                actual,
+            });
+        }
+
+        Ok(actual)
+    }
+}
+
+/// SHA-256 of the contents of the file at `path`.
+pub fn file_digest(path: &Path) -> io::Result<sha256::Hash> {
+    let mut file = File::open(path)?;
+    let mut engine = sha256::Hash::engine();
+    let mut buf = [0; 64 * 1024];
+    loop {
+        match file.read(&mut buf)? {
+            0 => return Ok(sha256::Hash::from_engine(engine)),
+            n => engine.input(&buf[..n]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_manifest() {
+        let dir = std::env::temp_dir().join("cwins-check-manifest");
+        std::fs::create_dir_all(&dir).unwrap();
+        let list = dir.join("list.txt");
+        std::fs::write(&list, "abc").unwrap();
+
+        let manifest = Manifest::parse(
+            "# published with the list\n\
+             ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  list.txt\n\
+             \n\
+             0000000000000000000000000000000000000000000000000000000000000000 *other.txt\n",
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.check(&list).unwrap().to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        std::fs::write(dir.join("other.txt"), "abc").unwrap();
+        assert!(matches!(
+            manifest.check(&dir.join("other.txt")),
+            Err(ManifestError::Mismatch { .. })
+        ));
+        assert!(matches!(
+            manifest.check(&dir.join("missing.txt")),
+            Err(ManifestError::Missing(_))
+        ));
+        assert!(matches!(
+            Manifest::parse("abc  list.txt"),
+            Err(ManifestError::Syntax(1))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+use bitcoin::address::NetworkChecked;
+use bitcoin::{Address, AddressType, Network, PublicKey};
+
+/// Decides whether a recovered public key is the key behind an address.
+///
+/// Implement this to plug in matching for address schemes the crate doesn't know
+/// about (forks, custom scripts) and pass it to [`crate::check_sig_with`].
+pub trait AddressMatcher {
+    fn matches(&self, pubkey: &PublicKey, address: &Address<NetworkChecked>) -> bool;
+}
+
+/// Matches the standard single-key address types: P2PKH, P2WPKH and P2SH-P2WPKH.
+pub struct StandardMatcher;
+
+impl AddressMatcher for StandardMatcher {
+    fn matches(&self, pubkey: &PublicKey, address: &Address<NetworkChecked>) -> bool {
+        let restored_address = match address.address_type() {
+            Some(AddressType::P2pkh) => Ok(Address::p2pkh(pubkey, address.network)),
+            Some(AddressType::P2wpkh) => Address::p2wpkh(pubkey, address.network),
+            Some(AddressType::P2sh) => Address::p2shwpkh(pubkey, address.network),
+            _ => return false,
+        };
+
+        match restored_address {
+            Ok(restored_address) => *address == restored_address,
+            // Uncompressed keys have no segwit address.
+            Err(_) => false,
+        }
+    }
+}
+
+/// Every standard address of `pubkey`; uncompressed keys only have a P2PKH one.
+pub fn standard_addresses(pubkey: &PublicKey, network: Network) -> Vec<Address> {
+    let mut addresses = vec![Address::p2pkh(pubkey, network)];
+    addresses.extend(Address::p2shwpkh(pubkey, network));
+    addresses.extend(Address::p2wpkh(pubkey, network));
+
+    addresses
+}
+
+/// The address of the same type as `address` for `pubkey`, the P2PKH one if `pubkey`
+/// has none of that type. Used to tell which address a mismatching signature is for.
+pub fn address_like(pubkey: &PublicKey, address: &Address<NetworkChecked>) -> Address {
+    standard_addresses(pubkey, address.network)
+        .into_iter()
+        .rev()
+        .find(|a| a.address_type() == address.address_type())
+        .unwrap_or_else(|| Address::p2pkh(pubkey, address.network))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_sig, check_sig_with, MESSAGE};
+    use bitcoin::secp256k1::{self, Secp256k1};
+
+    #[test]
+    fn check_standard_matcher() {
+        let secp = Secp256k1::new();
+        let sk = secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap();
+        let compressed = PublicKey::new(sk.public_key(&secp));
+        let uncompressed = PublicKey::new_uncompressed(sk.public_key(&secp));
+        let other = PublicKey::new(
+            secp256k1::SecretKey::from_slice(&[2u8; 32])
+                .unwrap()
+                .public_key(&secp),
+        );
+
+        let p2pkh = Address::p2pkh(&compressed, Network::Bitcoin);
+        let p2pkh_uncompressed = Address::p2pkh(&uncompressed, Network::Bitcoin);
+        let p2wpkh = Address::p2wpkh(&compressed, Network::Bitcoin).unwrap();
+        let p2shwpkh = Address::p2shwpkh(&compressed, Network::Bitcoin).unwrap();
+
+        for address in [&p2pkh, &p2wpkh, &p2shwpkh] {
+            assert!(StandardMatcher.matches(&compressed, address));
+            assert!(!StandardMatcher.matches(&other, address));
+        }
+        assert!(StandardMatcher.matches(&uncompressed, &p2pkh_uncompressed));
+        assert!(!StandardMatcher.matches(&uncompressed, &p2pkh));
+        assert!(!StandardMatcher.matches(&uncompressed, &p2wpkh));
+
+        assert_eq!(
+            standard_addresses(&compressed, Network::Bitcoin),
+            vec![p2pkh.clone(), p2shwpkh.clone(), p2wpkh.clone()]
+        );
+        assert_eq!(
+            standard_addresses(&uncompressed, Network::Bitcoin),
+            vec![p2pkh_uncompressed.clone()]
+        );
+
+        assert_eq!(
+            address_like(&other, &p2pkh),
+            Address::p2pkh(&other, Network::Bitcoin)
+        );
+        assert_eq!(
+            address_like(&other, &p2wpkh),
+            Address::p2wpkh(&other, Network::Bitcoin).unwrap()
+        );
+        assert_eq!(address_like(&uncompressed, &p2shwpkh), p2pkh_uncompressed);
+    }
+
+    #[test]
+    fn check_custom_matcher() {
+        struct AnyAddress;
+
+        impl AddressMatcher for AnyAddress {
+            fn matches(&self, _pubkey: &PublicKey, _address: &Address<NetworkChecked>) -> bool {
+                true
+            }
+        }
+
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let signature = "HFjd/SzCNDyXRY/skSjEKusK/adVtBf0ldT1ayvPb+WsLa5Qr0A4seEXjOmtg9K/wcJnv/E3F5TezZNB/ULoZI8=";
+
+        assert_eq!(
+            check_sig(address.clone(), MESSAGE, signature).ok(),
+            Some(false)
+        );
+        assert_eq!(
+            check_sig_with(&AnyAddress, address, MESSAGE, signature).ok(),
+            Some(true)
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{check_sig, VerifyError, MESSAGE};
+use bitcoin::address::NetworkChecked;
+use bitcoin::Address;
+use similar::TextDiff;
+
+/// Unified diff from the built-in [`MESSAGE`] to `message`, `None` if they are identical.
+pub fn message_diff(message: &str) -> Option<String> {
+    if message == MESSAGE {
+        return None;
+    }
+
+    Some(
+        TextDiff::from_lines(MESSAGE, message)
+            .unified_diff()
+            .header("built-in message", "supplied message")
+            .to_string(),
+    )
+}
+
+/// Decodes one line of a message list file. The escapes `\n`, `\r`, `\t` and
+/// `\\` let a single line describe a multi-line message exactly.
+pub fn unescape_message(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+
+    out
+}
+
+/// Parses a message list file (`--message-variants`, `--messages-file`): one escaped
+/// message per line, keyed by its (1-based) line number. Empty lines are skipped.
+pub fn parse_message_list(content: &str) -> Vec<(usize, String)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(i, line)| (i + 1, unescape_message(line)))
+        .collect()
+}
+
+/// Line number of the first variant the signature is valid for.
+pub fn find_variant(
+    address: &Address<NetworkChecked>,
+    signature: &str,
+    variants: &[(usize, String)],
+) -> Option<usize> {
+    variants
+        .iter()
+        .find(|(_, variant)| matches!(check_sig(address.clone(), variant, signature), Ok(true)))
+        .map(|(line, _)| *line)
+}
+
+/// The first of the candidate `messages` the signature is valid for, with its line
+/// number; an undecodable signature is an error for all of them.
+pub fn matching_message<'a>(
+    address: &Address<NetworkChecked>,
+    signature: &str,
+    messages: &'a [(usize, String)],
+) -> Result<Option<(usize, &'a str)>, VerifyError> {
+    for (line, message) in messages {
+        if check_sig(address.clone(), message, signature)? {
+            return Ok(Some((*line, message)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Line numbers of the messages the signature is not valid for.
+pub fn failed_messages(
+    address: &Address<NetworkChecked>,
+    signature: &str,
+    messages: &[(usize, String)],
+) -> Result<Vec<usize>, VerifyError> {
+    let mut failed = Vec::new();
+
+    for (line, message) in messages {
+        if !check_sig(address.clone(), message, signature)? {
+            failed.push(*line);
+        }
+    }
+
+    Ok(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::Network;
+
+    #[test]
+    fn check_message_diff() {
+        assert_eq!(message_diff(MESSAGE), None);
+
+        let diff = message_diff(&MESSAGE.replace("fraud.", "fraud. ")).unwrap();
+        assert!(diff.starts_with("--- built-in message\n+++ supplied message\n"));
+        assert!(diff.contains("\n-Craig Steven Wright is a liar and a fraud. He"));
+        assert!(diff.contains("\n+Craig Steven Wright is a liar and a fraud.  He"));
+        assert!(!diff.contains("-We are all Satoshi"));
+    }
+
+    #[test]
+    fn check_message_variants() {
+        assert_eq!(unescape_message(r"a\nb\r\n\tc\\n"), "a\nb\r\n\tc\\n");
+
+        let escaped = MESSAGE.replace('\n', "\\n");
+        let content = format!("{}\n\n{}\n", MESSAGE.lines().next().unwrap(), escaped);
+        let variants = parse_message_list(&content);
+        assert_eq!(variants.len(), 2);
+        assert_eq!(variants[1], (3, MESSAGE.to_string()));
+
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+        assert_eq!(find_variant(&address, signature, &variants), Some(3));
+        assert_eq!(find_variant(&address, signature, &variants[..1]), None);
+    }
+
+    #[test]
+    fn check_all_messages_required() {
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+            .parse::<Address<_>>()
+            .unwrap()
+            .require_network(Network::Bitcoin)
+            .unwrap();
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+        let messages = vec![
+            (1, MESSAGE.to_string()),
+            (2, "We are all Satoshi".to_string()),
+            (4, MESSAGE.to_string()),
+        ];
+
+        assert_eq!(
+            failed_messages(&address, signature, &messages).unwrap(),
+            vec![2]
+        );
+        assert!(failed_messages(&address, signature, &[messages[0].clone()])
+            .unwrap()
+            .is_empty());
+        assert!(failed_messages(&address, "bad", &messages).is_err());
+
+        assert_eq!(
+            matching_message(&address, signature, &messages[1..]).unwrap(),
+            Some((4, MESSAGE))
+        );
+        assert_eq!(
+            matching_message(&address, signature, &messages[1..2]).unwrap(),
+            None
+        );
+        assert!(matching_message(&address, "bad", &messages).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+// Prometheus metrics of the HTTP server, served as `GET /metrics` in the text
+// exposition format: requests by status, verifications by result and a histogram
+// of how long the verifications took.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const BUCKETS: [f64; 10] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.5, 1.0,
+];
+
+/// Result names of the verify endpoint, as labels.
+const RESULTS: [&str; 3] = ["valid", "invalid", "error"];
+
+/// Counters shared by the connection threads.
+#[derive(Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<u16, u64>>,
+    verifications: [AtomicU64; 3],
+    buckets: [AtomicU64; BUCKETS.len()],
+    /// Sum of the verification times in microseconds.
+    duration_sum: AtomicU64,
+}
+
+impl Metrics {
+    /// Counts an answered request.
+    pub fn request(&self, status: u16) {
+        *self
+            .requests
+            .lock()
+            .expect("metrics lock")
+            .entry(status)
+            .or_default() += 1;
+    }
+
+    /// Counts a verification with its result (`valid`, `invalid` or `error`).
+    pub fn verification(&self, result: &str, took: Duration) {
+        let index = RESULTS.iter().position(|r| *r == result).unwrap_or(2);
+        self.verifications[index].fetch_add(1, Ordering::Relaxed);
+
+        let seconds = took.as_secs_f64();
+        if let Some(bucket) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.duration_sum
+            .fetch_add(took.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// The metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+
+        text.push_str("# HELP cwins_http_requests_total HTTP requests answered, by status.\n");
+        text.push_str("# TYPE cwins_http_requests_total counter\n");
+        for (status, count) in self.requests.lock().expect("metrics lock").iter() {
+            let _ = writeln!(
+                text,
+                "cwins_http_requests_total{{status=\"{}\"}} {}",
+                status, count
+            );
+        }
+
+        text.push_str("# HELP cwins_verifications_total Signatures verified, by result.\n");
+        text.push_str("# TYPE cwins_verifications_total counter\n");
+        for (result, count) in RESULTS.iter().zip(&self.verifications) {
+            let _ = writeln!(
+                text,
+                "cwins_verifications_total{{result=\"{}\"}} {}",
+                result,
+                count.load(Ordering::Relaxed)
+            );
+        }
+
+        text.push_str("# HELP cwins_verification_duration_seconds Time taken by a verification.\n");
+        text.push_str("# TYPE cwins_verification_duration_seconds histogram\n");
+        // Buckets are cumulative, the last one is every verification.
+        let mut cumulative = 0;
+        for (bound, count) in BUCKETS.iter().zip(&self.buckets) {
+            cumulative += count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                text,
+                "cwins_verification_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound, cumulative
+            );
+        }
+        let total: u64 = self
+            .verifications
+            .iter()
+            .map(|c| c.load(Ordering::Relaxed))
+            .sum();
+        let _ = writeln!(
+            text,
+            "cwins_verification_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            total
+        );
+        let _ = writeln!(
+            text,
+            "cwins_verification_duration_seconds_sum {}",
+            self.duration_sum.load(Ordering::Relaxed) as f64 / 1e6
+        );
+        let _ = writeln!(text, "cwins_verification_duration_seconds_count {}", total);
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_metrics() {
+        let metrics = Metrics::default();
+        metrics.request(200);
+        metrics.request(200);
+        metrics.request(400);
+        metrics.verification("valid", Duration::from_micros(800));
+        metrics.verification("invalid", Duration::from_millis(20));
+        metrics.verification("error", Duration::from_secs(2));
+
+        let text = metrics.render();
+        assert!(text.contains("cwins_http_requests_total{status=\"200\"} 2\n"));
+        assert!(text.contains("cwins_http_requests_total{status=\"400\"} 1\n"));
+        assert!(text.contains("cwins_verifications_total{result=\"valid\"} 1\n"));
+        assert!(text.contains("cwins_verifications_total{result=\"error\"} 1\n"));
+        assert!(text.contains("cwins_verification_duration_seconds_bucket{le=\"0.0005\"} 0\n"));
+        assert!(text.contains("cwins_verification_duration_seconds_bucket{le=\"0.001\"} 1\n"));
+        assert!(text.contains("cwins_verification_duration_seconds_bucket{le=\"0.025\"} 2\n"));
+        assert!(text.contains("cwins_verification_duration_seconds_bucket{le=\"1\"} 2\n"));
+        assert!(text.contains("cwins_verification_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("cwins_verification_duration_seconds_sum 2.0208\n"));
+        assert!(text.contains("cwins_verification_duration_seconds_count 3\n"));
+    }
+}
@@ -0,0 +1,97 @@
+// Signing with a whole BIP-39 wallet (`sign --mnemonic-file`): the first addresses of
+// the receiving chain of an account each sign the message, giving the lines to add
+// to the list for all of them at once.
+
+use crate::sign::sign_message;
+use bip39::Mnemonic;
+use bitcoin::address::{self, NetworkChecked};
+use bitcoin::bip32::{self, ChildNumber, DerivationPath, ExtendedPrivKey};
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{Address, AddressType, Network, PrivateKey};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MnemonicError {
+    #[error("invalid mnemonic: {0}")]
+    Mnemonic(#[from] bip39::Error),
+    #[error("cannot derive the keys: {0}")]
+    Derivation(#[from] bip32::Error),
+    #[error("cannot sign: {0}")]
+    Address(#[from] address::Error),
+}
+
+/// The BIP-44/49/84 account `m/<purpose>h/0h/0h` of the address type.
+pub fn default_account(address_type: AddressType) -> DerivationPath {
+    let purpose = match address_type {
+        AddressType::P2sh => 49,
+        AddressType::P2wpkh => 84,
+        _ => 44,
+    };
+    format!("m/{}h/0h/0h", purpose)
+        .parse()
+        .expect("a valid derivation path")
+}
+
+/// Signs `message` with the keys `<account>/0/0` .. `<account>/0/<count-1>` of the
+/// wallet of `mnemonic` (without a passphrase) and returns their addresses of
+/// `address_type` with the signatures.
+pub fn sign_with_mnemonic(
+    mnemonic: &str,
+    account: &DerivationPath,
+    count: u32,
+    message: &str,
+    address_type: AddressType,
+) -> Result<Vec<(Address<NetworkChecked>, String)>, MnemonicError> {
+    let seed = Mnemonic::parse(mnemonic.trim())?.to_seed("");
+    let secp = Secp256k1::new();
+    let master = ExtendedPrivKey::new_master(Network::Bitcoin, &seed)?;
+    let chain = master
+        .derive_priv(&secp, account)?
+        .ckd_priv(&secp, ChildNumber::from_normal_idx(0)?)?;
+
+    (0..count)
+        .map(|i| {
+            let child = chain.ckd_priv(&secp, ChildNumber::from_normal_idx(i)?)?;
+            let key = PrivateKey::new(child.private_key, Network::Bitcoin);
+            Ok(sign_message(&key, message, address_type)?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_sig, MESSAGE};
+
+    /// The test vector mnemonic of BIP-84.
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+                            abandon abandon abandon about";
+
+    #[test]
+    fn check_sign_with_mnemonic() {
+        let account = default_account(AddressType::P2wpkh);
+        assert_eq!(account.to_string(), "m/84'/0'/0'");
+
+        let lines =
+            sign_with_mnemonic(MNEMONIC, &account, 2, MESSAGE, AddressType::P2wpkh).unwrap();
+        let addresses: Vec<String> = lines.iter().map(|(a, _)| a.to_string()).collect();
+        assert_eq!(
+            addresses,
+            [
+                "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+                "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g"
+            ]
+        );
+        for (address, signature) in lines {
+            assert!(check_sig(address, MESSAGE, &signature).unwrap());
+        }
+
+        let legacy = default_account(AddressType::P2pkh);
+        let lines = sign_with_mnemonic(MNEMONIC, &legacy, 1, MESSAGE, AddressType::P2pkh).unwrap();
+        assert_eq!(lines[0].0.to_string(), "1LqBGSKuX5yYUonjxT5qGfpUsXKYYWeabA");
+
+        assert!(matches!(
+            sign_with_mnemonic("abandon abandon", &legacy, 1, MESSAGE, AddressType::P2pkh),
+            Err(MnemonicError::Mnemonic(_))
+        ));
+    }
+}
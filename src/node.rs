@@ -0,0 +1,119 @@
+// Node.js bindings (an N-API addon) for verifying submitted signatures on a server,
+// built with
+//
+//     napi build --platform --release --features node
+//
+// and used as
+//
+//     const { verify, verifyAsync } = require("./craigwrightisnotsatoshi-check.node");
+//     const result = verify(address, signature);  // or verify(address, signature, message)
+//     const result = await verifyAsync(address, signature);
+//     result.valid, result.error
+
+use crate::{verify_message_on, MESSAGE};
+use bitcoin::Network;
+use napi::bindgen_prelude::AsyncTask;
+use napi::{Env, Result, Task};
+use napi_derive::napi;
+
+/// Outcome of one check; `error` is one of the codes of the JSON output.
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+fn check(address: &str, signature: &str, message: Option<&str>) -> Verification {
+    let message = message.unwrap_or(MESSAGE);
+
+    match verify_message_on(Network::Bitcoin, address, message, signature) {
+        Ok(valid) => Verification { valid, error: None },
+        Err(e) => Verification {
+            valid: false,
+            error: Some(e.code().to_string()),
+        },
+    }
+}
+
+/// Checks a signature of `message` (the built-in one if omitted) by a mainnet address.
+#[napi]
+pub fn verify(address: String, signature: String, message: Option<String>) -> Verification {
+    check(&address, &signature, message.as_deref())
+}
+
+/// A [`verify`] on the libuv thread pool.
+pub struct VerifyTask {
+    address: String,
+    signature: String,
+    message: Option<String>,
+}
+
+impl Task for VerifyTask {
+    type Output = Verification;
+    type JsValue = Verification;
+
+    fn compute(&mut self) -> Result<Verification> {
+        Ok(check(
+            &self.address,
+            &self.signature,
+            self.message.as_deref(),
+        ))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Verification) -> Result<Verification> {
+        Ok(output)
+    }
+}
+
+/// Like [`verify`], without blocking the event loop: the promise resolves to the
+/// result once a worker thread has checked the signature.
+#[napi]
+pub fn verify_async(
+    address: String,
+    signature: String,
+    message: Option<String>,
+) -> AsyncTask<VerifyTask> {
+    AsyncTask::new(VerifyTask {
+        address,
+        signature,
+        message,
+    })
+}
+
+/// The statement signed by the addresses on the list.
+#[napi]
+pub fn default_message() -> String {
+    MESSAGE.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_node_verify() {
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m";
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+
+        assert!(check(address, signature, None).valid);
+        assert_eq!(
+            check(address, signature, Some("other")),
+            Verification {
+                valid: false,
+                error: None
+            }
+        );
+        assert_eq!(
+            check("1Fb", signature, None).error.as_deref(),
+            Some("address_parse")
+        );
+
+        let mut task = VerifyTask {
+            address: address.to_string(),
+            signature: signature.to_string(),
+            message: None,
+        };
+        assert!(task.compute().unwrap().valid);
+    }
+}
@@ -0,0 +1,181 @@
+// Normalization of the message before hashing: Unicode forms (`--normalize`) and line
+// endings (`--message-newlines`). A message copied through a web page or saved on
+// another system can come out in another form than the one that was signed, which
+// changes its hash without changing how it looks.
+
+use std::borrow::Cow;
+use std::str::FromStr;
+use unicode_normalization::{is_nfc, is_nfd, UnicodeNormalization};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Normalization {
+    /// The message as given.
+    #[default]
+    None,
+    /// Canonical composition.
+    Nfc,
+    /// Compatibility composition, also folding e.g. ligatures and full-width forms.
+    Nfkc,
+}
+
+impl Normalization {
+    pub fn apply(self, message: &str) -> Cow<'_, str> {
+        match self {
+            Normalization::None => Cow::Borrowed(message),
+            Normalization::Nfc if is_nfc(message) => Cow::Borrowed(message),
+            Normalization::Nfc => Cow::Owned(message.nfc().collect()),
+            Normalization::Nfkc => Cow::Owned(message.nfkc().collect()),
+        }
+    }
+}
+
+impl FromStr for Normalization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Normalization::None),
+            "nfc" => Ok(Normalization::Nfc),
+            "nfkc" => Ok(Normalization::Nfkc),
+            _ => Err(format!(
+                "unknown normalization `{}`, expected nfc, nfkc or none",
+                s
+            )),
+        }
+    }
+}
+
+/// Line endings of the hashed message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Newlines {
+    /// The line endings as given.
+    #[default]
+    Keep,
+    /// `\n` only.
+    Lf,
+    /// `\r\n` only.
+    Crlf,
+}
+
+impl Newlines {
+    pub fn apply(self, message: &str) -> Cow<'_, str> {
+        match self {
+            Newlines::Keep => Cow::Borrowed(message),
+            Newlines::Lf if !message.contains('\r') => Cow::Borrowed(message),
+            Newlines::Lf => Cow::Owned(message.replace("\r\n", "\n")),
+            Newlines::Crlf => Cow::Owned(message.replace("\r\n", "\n").replace('\n', "\r\n")),
+        }
+    }
+}
+
+impl FromStr for Newlines {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(Newlines::Keep),
+            "lf" => Ok(Newlines::Lf),
+            "crlf" => Ok(Newlines::Crlf),
+            _ => Err(format!(
+                "unknown line ending `{}`, expected lf, crlf or keep",
+                s
+            )),
+        }
+    }
+}
+
+/// Typographic characters that usually replace the ASCII ones when text is copied
+/// from a web page or a word processor.
+const LOOKALIKES: [char; 6] = [
+    '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2013}', '\u{a0}',
+];
+
+/// Warnings about the characters of `message` that may keep a signature from
+/// verifying: non-ASCII ones, typographic lookalikes and a normalization other than
+/// NFC (or a mix of forms).
+pub fn diagnose(message: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let mut non_ascii: Vec<char> = message.chars().filter(|c| !c.is_ascii()).collect();
+    if non_ascii.is_empty() {
+        return warnings;
+    }
+    non_ascii.sort_unstable();
+    non_ascii.dedup();
+    let names: Vec<String> = non_ascii
+        .iter()
+        .take(8)
+        .map(|c| format!("U+{:04X}", *c as u32))
+        .collect();
+    warnings.push(format!(
+        "The message contains {} distinct non-ASCII characters: {}{}",
+        non_ascii.len(),
+        names.join(", "),
+        if non_ascii.len() > names.len() {
+            ", ..."
+        } else {
+            ""
+        }
+    ));
+
+    if non_ascii.iter().any(|c| LOOKALIKES.contains(c)) {
+        warnings.push(
+            "The message contains typographic quotes, dashes or non-breaking spaces, \
+             the signed text may have had ASCII ones"
+                .to_string(),
+        );
+    }
+
+    if !is_nfc(message) {
+        warnings.push(if is_nfd(message) {
+            "The message is in NFD form, try --normalize nfc".to_string()
+        } else {
+            "The message mixes Unicode normalization forms, try --normalize nfc".to_string()
+        });
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_normalization() {
+        let composed = "We are all Satoshi, caf\u{e9}";
+        let decomposed = "We are all Satoshi, cafe\u{301}";
+        assert_eq!(Normalization::Nfc.apply(decomposed), composed);
+        assert!(matches!(
+            Normalization::Nfc.apply(composed),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(Normalization::None.apply(decomposed), decomposed);
+        assert_eq!(Normalization::Nfkc.apply("\u{fb01}x"), "fix");
+        assert_eq!("nfkc".parse(), Ok(Normalization::Nfkc));
+        assert!("nfd".parse::<Normalization>().is_err());
+
+        let mixed = "line one\r\nline two\nline three";
+        assert_eq!(Newlines::Lf.apply(mixed), "line one\nline two\nline three");
+        assert_eq!(
+            Newlines::Crlf.apply(mixed),
+            "line one\r\nline two\r\nline three"
+        );
+        assert_eq!(Newlines::Keep.apply(mixed), mixed);
+        assert!(matches!(
+            Newlines::Lf.apply(crate::MESSAGE),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!("crlf".parse(), Ok(Newlines::Crlf));
+
+        assert!(diagnose(crate::MESSAGE).is_empty());
+        assert_eq!(diagnose(composed).len(), 1);
+        let warnings = diagnose(decomposed);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("U+0301"));
+        assert!(warnings[1].contains("NFD"));
+        let warnings = diagnose("\u{201c}caf\u{e9}\u{201d} cafe\u{301}");
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[2].contains("mixes"));
+    }
+}
@@ -0,0 +1,336 @@
+// OpenTimestamps [0] proofs of the reports. Stamping submits the SHA-256 of a report
+// to a calendar server, whose answer is a pending timestamp: the operations from the
+// digest to the commitment the calendar later puts into a Bitcoin block. Verifying
+// walks those operations, asks the calendars for the finished part of the pending
+// ones and compares the Bitcoin ones with the merkle root of the block.
+//
+// Refs.:
+// - [0] https://opentimestamps.org/
+
+use bitcoin::hashes::{ripemd160, sha1, sha256, Hash};
+use serde_json::Value;
+use std::io::Read;
+
+/// Calendar of the reference client.
+pub const DEFAULT_CALENDAR: &str = "https://a.pool.opentimestamps.org";
+
+/// Esplora API the block merkle roots are taken from.
+pub const DEFAULT_ESPLORA: &str = "https://blockstream.info/api";
+
+const MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+const VERSION: u8 = 1;
+const OP_SHA256: u8 = 0x08;
+const BITCOIN_TAG: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+const PENDING_TAG: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+
+/// What a commitment is attested by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attestation {
+    /// Not in a block yet, the calendar at the URL knows how it continues.
+    Pending(String),
+    /// The merkle root of the block at this height.
+    Bitcoin(u32),
+    /// An attestation this client doesn't know, e.g. of another chain.
+    Unknown([u8; 8]),
+}
+
+/// A commitment and its attestation.
+pub type Attested = (Vec<u8>, Attestation);
+
+/// A parsed `.ots` file: the digest of the stamped file and every attestation with
+/// the commitment it attests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proof {
+    pub digest: sha256::Hash,
+    pub attestations: Vec<Attested>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < n {
+            return Err("truncated timestamp".to_string());
+        }
+        let (head, rest) = self.data.split_at(n);
+        self.data = rest;
+        Ok(head)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// LEB128, as everywhere in the format.
+    fn varuint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            value |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varuint too long".to_string())
+    }
+
+    fn varbytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.varuint()? as usize;
+        self.bytes(len)
+    }
+}
+
+fn attestation(reader: &mut Reader) -> Result<Attestation, String> {
+    let tag: [u8; 8] = reader.bytes(8)?.try_into().expect("8 bytes");
+    let mut payload = Reader {
+        data: reader.varbytes()?,
+    };
+
+    match tag {
+        BITCOIN_TAG => Ok(Attestation::Bitcoin(payload.varuint()? as u32)),
+        PENDING_TAG => {
+            let url = String::from_utf8(payload.varbytes()?.to_vec())
+                .map_err(|_| "calendar URL isn't UTF-8".to_string())?;
+            Ok(Attestation::Pending(url))
+        }
+        _ => Ok(Attestation::Unknown(tag)),
+    }
+}
+
+/// Applies the operation of `tag` to `message`.
+fn operation(tag: u8, reader: &mut Reader, message: Vec<u8>) -> Result<Vec<u8>, String> {
+    Ok(match tag {
+        OP_SHA256 => sha256::Hash::hash(&message).to_byte_array().to_vec(),
+        0x02 => sha1::Hash::hash(&message).to_byte_array().to_vec(),
+        0x03 => ripemd160::Hash::hash(&message).to_byte_array().to_vec(),
+        0xf0 => [message.as_slice(), reader.varbytes()?].concat(),
+        0xf1 => [reader.varbytes()?, message.as_slice()].concat(),
+        0xf2 => message.into_iter().rev().collect(),
+        0xf3 => message
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+            .into_bytes(),
+        _ => return Err(format!("unsupported operation 0x{:02x}", tag)),
+    })
+}
+
+/// Walks a serialized timestamp of `message`: any number of `0xff` forks, each
+/// followed by a branch, then the last branch.
+fn walk(
+    reader: &mut Reader,
+    message: Vec<u8>,
+    attestations: &mut Vec<Attested>,
+) -> Result<(), String> {
+    loop {
+        match reader.byte()? {
+            0xff => {
+                let tag = reader.byte()?;
+                branch(reader, tag, message.clone(), attestations)?;
+            }
+            tag => return branch(reader, tag, message, attestations),
+        }
+    }
+}
+
+fn branch(
+    reader: &mut Reader,
+    tag: u8,
+    message: Vec<u8>,
+    attestations: &mut Vec<Attested>,
+) -> Result<(), String> {
+    if tag == 0x00 {
+        attestations.push((message, attestation(reader)?));
+        return Ok(());
+    }
+
+    let message = operation(tag, reader, message)?;
+    walk(reader, message, attestations)
+}
+
+/// The attestations of a serialized timestamp of `message`.
+pub fn parse_timestamp(data: &[u8], message: &[u8]) -> Result<Vec<Attested>, String> {
+    let mut reader = Reader { data };
+    let mut attestations = Vec::new();
+    walk(&mut reader, message.to_vec(), &mut attestations)?;
+    if !reader.data.is_empty() {
+        return Err("garbage after the timestamp".to_string());
+    }
+
+    Ok(attestations)
+}
+
+/// Parses a `.ots` file of a SHA-256 digest.
+pub fn parse_proof(data: &[u8]) -> Result<Proof, String> {
+    let rest = data
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| "not an OpenTimestamps proof".to_string())?;
+    let mut reader = Reader { data: rest };
+    if reader.varuint()? != VERSION as u64 {
+        return Err("unsupported proof version".to_string());
+    }
+    if reader.byte()? != OP_SHA256 {
+        return Err("only SHA-256 proofs are supported".to_string());
+    }
+    let digest = sha256::Hash::from_slice(reader.bytes(32)?).expect("32 bytes");
+
+    Ok(Proof {
+        digest,
+        attestations: parse_timestamp(reader.data, digest.as_byte_array())?,
+    })
+}
+
+fn read_body(response: ureq::Response) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| e.to_string())?;
+
+    Ok(body)
+}
+
+/// Submits `digest` to `calendar` and returns the `.ots` file with its pending
+/// timestamp.
+pub fn stamp(digest: sha256::Hash, calendar: &str) -> Result<Vec<u8>, String> {
+    let url = format!("{}/digest", calendar.trim_end_matches('/'));
+    let response = ureq::post(&url)
+        .set("Accept", "application/vnd.opentimestamps.v1")
+        .send_bytes(digest.as_byte_array())
+        .map_err(|e| e.to_string())?;
+    let timestamp = read_body(response)?;
+    // Refuse to write a proof that couldn't be read back.
+    parse_timestamp(&timestamp, digest.as_byte_array())?;
+
+    let mut proof = MAGIC.to_vec();
+    proof.push(VERSION);
+    proof.push(OP_SHA256);
+    proof.extend_from_slice(digest.as_byte_array());
+    proof.extend_from_slice(&timestamp);
+    Ok(proof)
+}
+
+/// The part of a pending timestamp the calendar at `url` has finished since,
+/// `Ok(None)` while the commitment isn't in a block yet.
+pub fn upgrade(url: &str, commitment: &[u8]) -> Result<Option<Vec<Attested>>, String> {
+    let hex: String = commitment.iter().map(|b| format!("{:02x}", b)).collect();
+    let response = ureq::get(&format!("{}/timestamp/{}", url.trim_end_matches('/'), hex))
+        .set("Accept", "application/vnd.opentimestamps.v1")
+        .call();
+
+    match response {
+        Ok(response) => parse_timestamp(&read_body(response)?, commitment).map(Some),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// The merkle root of the block at `height`, in the byte order commitments use.
+pub fn merkle_root(esplora: &str, height: u32) -> Result<Vec<u8>, String> {
+    let esplora = esplora.trim_end_matches('/');
+    let get = |url: String| -> Result<String, String> {
+        ureq::get(&url)
+            .call()
+            .map_err(|e| e.to_string())?
+            .into_string()
+            .map_err(|e| e.to_string())
+    };
+    let hash = get(format!("{}/block-height/{}", esplora, height))?;
+    let block: Value = serde_json::from_str(&get(format!("{}/block/{}", esplora, hash.trim()))?)
+        .map_err(|e| e.to_string())?;
+
+    let root: sha256::Hash = block["merkle_root"]
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| "no merkle_root in the block".to_string())?;
+    // Block explorers show it reversed, like every other hash.
+    Ok(root.to_byte_array().iter().rev().copied().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn varbytes(data: &[u8]) -> Vec<u8> {
+        [&[data.len() as u8], data].concat()
+    }
+
+    #[test]
+    fn check_ots_proof() {
+        let digest = sha256::Hash::hash(b"OK - 1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m\n");
+
+        // append, sha256, then a fork into a pending and a Bitcoin attestation.
+        let url = b"https://alice.example";
+        let mut timestamp = vec![0xf0];
+        timestamp.extend(varbytes(b"nonce"));
+        timestamp.extend([OP_SHA256, 0xff, 0x00]);
+        timestamp.extend(PENDING_TAG);
+        timestamp.extend(varbytes(&varbytes(url)));
+        timestamp.extend([0xf1]);
+        timestamp.extend(varbytes(b"prefix"));
+        timestamp.push(0x00);
+        timestamp.extend(BITCOIN_TAG);
+        // Height 300000 as LEB128.
+        timestamp.extend(varbytes(&[0xe0, 0xa7, 0x12]));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let calendar = format!("http://{}", listener.local_addr().unwrap());
+        let answer = timestamp.clone();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut digest = [0; 32];
+            reader.read_exact(&mut digest).unwrap();
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                answer.len()
+            )
+            .unwrap();
+            reader.get_mut().write_all(&answer).unwrap();
+            (request, digest)
+        });
+
+        let proof = stamp(digest, &calendar).unwrap();
+        let (request, submitted) = server.join().unwrap();
+        assert!(request.starts_with("POST /digest "));
+        assert_eq!(&submitted, digest.as_byte_array());
+
+        let parsed = parse_proof(&proof).unwrap();
+        assert_eq!(parsed.digest, digest);
+        let commitment = sha256::Hash::hash(&[digest.as_byte_array(), &b"nonce"[..]].concat())
+            .to_byte_array()
+            .to_vec();
+        assert_eq!(
+            parsed.attestations,
+            [
+                (
+                    commitment.clone(),
+                    Attestation::Pending("https://alice.example".to_string())
+                ),
+                (
+                    [&b"prefix"[..], &commitment].concat(),
+                    Attestation::Bitcoin(300_000)
+                ),
+            ]
+        );
+
+        assert!(parse_proof(&proof[..proof.len() - 1]).is_err());
+        assert!(parse_proof(b"not a proof").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+// Atomically replaced output files (`--output`): the report is written to a temporary
+// file next to the target, synced and renamed over it only once complete, so an
+// interrupted run never leaves a truncated report behind.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub struct AtomicFile {
+    path: PathBuf,
+    temp: PathBuf,
+    file: Option<BufWriter<File>>,
+}
+
+impl AtomicFile {
+    /// Starts writing the new content of `path`.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp = path.with_file_name(temp_name);
+
+        let file = File::create(&temp)?;
+        Ok(AtomicFile {
+            path: path.to_path_buf(),
+            temp,
+            file: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// Syncs the written content and moves it to the path. Writing after this fails.
+    pub fn commit(&mut self) -> io::Result<()> {
+        let file = match self.file.take() {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        let file = file.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&self.temp, &self.path)?;
+
+        // Make the rename itself durable.
+        #[cfg(unix)]
+        if let Some(dir) = self.path.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            File::open(dir)?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Throws the new content away, leaving `path` as it was.
+    pub fn discard(&mut self) -> io::Result<()> {
+        match self.file.take() {
+            Some(_) => fs::remove_file(&self.temp),
+            None => Ok(()),
+        }
+    }
+
+    fn file(&mut self) -> io::Result<&mut BufWriter<File>> {
+        self.file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("output already committed"))
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file()?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file()?.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    /// An uncommitted file is thrown away.
+    fn drop(&mut self) {
+        let _ = self.discard();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_atomic_file() {
+        let dir = std::env::temp_dir().join("cwins-check-output-test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("results.txt");
+        fs::write(&path, "old report\n").unwrap();
+
+        // Dropped without a commit: the old report stays, no temporary file is left.
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"OK - 1Fb\n").unwrap();
+        drop(file);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old report\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"OK - 1Fb\n").unwrap();
+        file.discard().unwrap();
+        assert!(file.write_all(b"more").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old report\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let mut file = AtomicFile::create(&path).unwrap();
+        file.write_all(b"OK - 1Fb\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old report\n");
+        file.commit().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "OK - 1Fb\n");
+        assert!(file.write_all(b"more").is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,130 @@
+// Bounded producer/consumer hand-off for the input records. A reader thread parses
+// ahead while the caller verifies, at most `depth` batches wait in the channel and
+// the emptied batch vectors go back to the reader to be refilled, so memory stays
+// flat however long the input is.
+
+use std::panic;
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub struct Pipeline<T> {
+    batches: Receiver<Vec<T>>,
+    recycled: SyncSender<Vec<T>>,
+    reader: Option<JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> Pipeline<T> {
+    /// Starts pulling `items` on a new thread in batches of `batch_size`.
+    pub fn new<I>(items: I, batch_size: usize, depth: usize) -> Self
+    where
+        I: Iterator<Item = T> + Send + 'static,
+    {
+        let batch_size = batch_size.max(1);
+        let (sender, batches) = sync_channel(depth);
+        let (recycled, returned) = sync_channel::<Vec<T>>(depth + 2);
+
+        let reader = thread::spawn(move || {
+            let mut items = items;
+            loop {
+                let mut batch = returned
+                    .try_recv()
+                    .unwrap_or_else(|_| Vec::with_capacity(batch_size));
+                batch.extend(items.by_ref().take(batch_size));
+                // Stop at the end of the input or once the consumer has gone away.
+                if batch.is_empty() || sender.send(batch).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Pipeline {
+            batches,
+            recycled,
+            reader: Some(reader),
+        }
+    }
+
+    /// The next batch in input order, `None` at the end. A panic of the reader
+    /// thread is resumed here.
+    pub fn next_batch(&mut self) -> Option<Vec<T>> {
+        match self.batches.recv() {
+            Ok(batch) => Some(batch),
+            Err(_) => self.finish(),
+        }
+    }
+
+    /// Like [`Pipeline::next_batch`], but stops waiting for the reader (`None`) once
+    /// `stop` returns true, which is checked every `poll`.
+    pub fn next_batch_unless(&mut self, poll: Duration, stop: impl Fn() -> bool) -> Option<Vec<T>> {
+        loop {
+            if stop() {
+                return None;
+            }
+            match self.batches.recv_timeout(poll) {
+                Ok(batch) => return Some(batch),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return self.finish(),
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Option<Vec<T>> {
+        if let Some(reader) = self.reader.take() {
+            if let Err(payload) = reader.join() {
+                panic::resume_unwind(payload);
+            }
+        }
+        None
+    }
+
+    /// Hands an emptied batch back to the reader to reuse its allocation.
+    pub fn recycle(&self, mut batch: Vec<T>) {
+        batch.clear();
+        let _ = self.recycled.try_send(batch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_pipeline() {
+        let mut pipeline = Pipeline::new(0..10_000, 64, 2);
+        let mut seen = Vec::new();
+        let mut buffers = Vec::new();
+        while let Some(batch) = pipeline.next_batch() {
+            assert!(batch.len() <= 64);
+            seen.extend_from_slice(&batch);
+            buffers.push(batch.as_ptr() as usize);
+            pipeline.recycle(batch);
+        }
+        assert_eq!(seen, (0..10_000).collect::<Vec<_>>());
+        assert!(pipeline.next_batch().is_none());
+
+        // Only a handful of allocations are cycled around.
+        buffers.sort_unstable();
+        buffers.dedup();
+        assert!(buffers.len() <= 8);
+
+        let mut empty = Pipeline::new(std::iter::empty::<u8>(), 8, 1);
+        assert!(empty.next_batch().is_none());
+
+        // A reader waiting for more input doesn't hold up a stop.
+        let (sender, items) = std::sync::mpsc::channel();
+        let mut waiting = Pipeline::new(items.into_iter(), 1, 1);
+        sender.send(7).unwrap();
+        let poll = Duration::from_millis(5);
+        assert_eq!(waiting.next_batch_unless(poll, || false), Some(vec![7]));
+        let stopped = std::cell::Cell::new(false);
+        let stop = || {
+            let stop = stopped.get();
+            stopped.set(true);
+            stop
+        };
+        assert!(waiting.next_batch_unless(poll, stop).is_none());
+        drop(sender);
+        assert!(waiting.next_batch().is_none());
+    }
+}
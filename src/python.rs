@@ -0,0 +1,94 @@
+// Python bindings for analyzing the list from pandas or Jupyter with the verification
+// code of the CLI, built with
+//
+//     maturin develop --release
+//
+// (`pyproject.toml` turns on `--features python`) and used as
+//
+//     from craigwrightisnotsatoshi_check import verify, verify_batch, MESSAGE
+//     verify(address, MESSAGE, signature)
+//     df["valid"] = verify_batch(df.address.tolist(), df.signature.tolist())
+
+use crate::{error_reason, verify_message_on, Verifier, MESSAGE};
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::{Address, Network};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Whether `signature` is a valid one of `message` by the mainnet `address`. Raises
+/// `ValueError` if it cannot be checked at all, e.g. for a malformed address.
+#[pyfunction]
+pub fn verify(address: &str, message: &str, signature: &str) -> PyResult<bool> {
+    verify_message_on(Network::Bitcoin, address, message, signature)
+        .map_err(|e| PyValueError::new_err(format!("{} ({})", error_reason(&e), e.code())))
+}
+
+/// The results of the `signatures` of `message` (the built-in one if omitted) by the
+/// `addresses` in the same order, `None` where a signature cannot be checked. The
+/// message is hashed once for all of them.
+#[pyfunction]
+#[pyo3(signature = (addresses, signatures, message = None))]
+pub fn verify_batch(
+    addresses: Vec<String>,
+    signatures: Vec<String>,
+    message: Option<&str>,
+) -> PyResult<Vec<Option<bool>>> {
+    if addresses.len() != signatures.len() {
+        return Err(PyValueError::new_err(format!(
+            "{} addresses but {} signatures",
+            addresses.len(),
+            signatures.len()
+        )));
+    }
+
+    let verifier = Verifier::new(message.unwrap_or(MESSAGE));
+    Ok(addresses
+        .iter()
+        .zip(&signatures)
+        .map(|(address, signature)| {
+            let address = address
+                .parse::<Address<NetworkUnchecked>>()
+                .ok()?
+                .require_network(Network::Bitcoin)
+                .ok()?;
+            verifier.verify_one(&address, signature).ok()
+        })
+        .collect())
+}
+
+#[pymodule]
+fn craigwrightisnotsatoshi_check(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("MESSAGE", MESSAGE)?;
+    m.add_function(wrap_pyfunction!(verify, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_batch, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m";
+    const SIGNATURE: &str =
+        "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+
+    #[test]
+    fn check_python_verify() {
+        assert!(verify(ADDRESS, MESSAGE, SIGNATURE).unwrap());
+        assert!(!verify(ADDRESS, "other", SIGNATURE).unwrap());
+        assert!(verify("1Fb", MESSAGE, SIGNATURE).is_err());
+
+        let strings = |s: &[&str]| s.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let results = verify_batch(
+            strings(&[ADDRESS, ADDRESS, "1Fb"]),
+            strings(&[SIGNATURE, "not base64!", SIGNATURE]),
+            None,
+        );
+        assert_eq!(results.unwrap(), [Some(true), None, None]);
+        assert_eq!(
+            verify_batch(strings(&[ADDRESS]), strings(&[SIGNATURE]), Some("other")).unwrap(),
+            [Some(false)]
+        );
+        assert!(verify_batch(strings(&[ADDRESS]), Vec::new(), None).is_err());
+    }
+}
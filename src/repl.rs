@@ -0,0 +1,210 @@
+// The `repl` subcommand for one-off manual checks: an `<address> <signature>` line
+// (or an address alone, then prompted for the signature and the message) is verified
+// right away, `:` commands recover keys, explain BAD verdicts or change the message.
+// The line editing and history are main's, this is the part answering the lines.
+
+use crate::input::parse_line;
+use crate::matcher::{address_like, standard_addresses};
+use crate::messages::unescape_message;
+use crate::signature::recover_pubkey;
+use crate::{check_sig, error_reason, MESSAGE};
+use bitcoin::address::{NetworkChecked, NetworkUnchecked};
+use bitcoin::{Address, Network};
+
+const HELP: &str = "\
+<address> <signature>     verify with the current message
+<address>                 verify, asking for the signature and the message
+:message [<text>]         show or set the current message (`\\n` for newlines)
+:message default          go back to the built-in message
+:recover <signature>      the key and the addresses that made a signature
+:explain <address> <sig>  why a signature is BAD for an address
+:help                     this help
+:quit                     leave (or Ctrl-D)";
+
+/// What the line being typed is for.
+#[derive(Debug, Clone, PartialEq)]
+enum State {
+    Ready,
+    Signature(String),
+    Message(String, String),
+}
+
+#[derive(Debug)]
+pub struct Repl {
+    message: String,
+    network: Network,
+    state: State,
+}
+
+impl Repl {
+    pub fn new(message: &str, network: Network) -> Self {
+        Repl {
+            message: message.to_string(),
+            network,
+            state: State::Ready,
+        }
+    }
+
+    pub fn prompt(&self) -> &'static str {
+        match self.state {
+            State::Ready => "cwins> ",
+            State::Signature(_) => "signature> ",
+            State::Message(..) => "message (empty for the current one)> ",
+        }
+    }
+
+    /// The answer to a typed line, `None` to leave.
+    pub fn handle(&mut self, line: &str) -> Option<Vec<String>> {
+        let line = line.trim();
+        match std::mem::replace(&mut self.state, State::Ready) {
+            State::Signature(address) => {
+                self.state = State::Message(address, line.to_string());
+                Some(Vec::new())
+            }
+            State::Message(address, signature) => {
+                let message = match line {
+                    "" => self.message.clone(),
+                    message => unescape_message(message),
+                };
+                Some(vec![self.verify(&address, &signature, &message)])
+            }
+            State::Ready if line.starts_with(':') => self.command(line),
+            State::Ready if line.is_empty() => Some(Vec::new()),
+            State::Ready => Some(match parse_line(line) {
+                Some(record) => {
+                    let message = record.message.as_deref().unwrap_or(&self.message);
+                    vec![self.verify(&record.address, &record.signature, message)]
+                }
+                None if !line.contains(char::is_whitespace) => {
+                    self.state = State::Signature(line.to_string());
+                    Vec::new()
+                }
+                None => vec!["expected `<address> <signature>`, see :help".to_string()],
+            }),
+        }
+    }
+
+    fn command(&mut self, line: &str) -> Option<Vec<String>> {
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        let lines = match (command, rest) {
+            (":quit" | ":q", _) => return None,
+            (":help", _) => HELP.lines().map(str::to_string).collect(),
+            (":message", "") => vec![format!("{:?}", self.message)],
+            (":message", "default") => {
+                self.message = MESSAGE.to_string();
+                vec!["message reset to the built-in one".to_string()]
+            }
+            (":message", message) => {
+                self.message = unescape_message(message);
+                vec![format!("message set to {:?}", self.message)]
+            }
+            (":recover", signature) if !signature.is_empty() => self.recover(signature),
+            (":explain", args) => match args.split_whitespace().collect::<Vec<_>>()[..] {
+                [address, signature] => self.explain(address, signature),
+                _ => vec!["usage: :explain <address> <signature>".to_string()],
+            },
+            _ => vec![format!("unknown command {}, see :help", line)],
+        };
+
+        Some(lines)
+    }
+
+    fn address(&self, address: &str) -> Result<Address<NetworkChecked>, String> {
+        address
+            .parse::<Address<NetworkUnchecked>>()
+            .map_err(|e| error_reason(&e))?
+            .require_network(self.network)
+            .map_err(|e| error_reason(&e))
+    }
+
+    /// `OK - <address>`, `BAD - <address>` or `ERROR - <address>: <reason>`.
+    fn verify(&self, address: &str, signature: &str, message: &str) -> String {
+        let result = self
+            .address(address)
+            .and_then(|a| check_sig(a, message, signature).map_err(|e| error_reason(&e)));
+        match result {
+            Ok(true) => format!("OK - {}", address),
+            Ok(false) => format!("BAD - {} (:explain to see who signed)", address),
+            Err(reason) => format!("ERROR - {}: {}", address, reason),
+        }
+    }
+
+    fn recover(&self, signature: &str) -> Vec<String> {
+        match recover_pubkey(&self.message, signature) {
+            Ok(pubkey) => std::iter::once(format!("pubkey {}", pubkey))
+                .chain(
+                    standard_addresses(&pubkey, self.network)
+                        .iter()
+                        .map(|a| format!("address {}", a)),
+                )
+                .collect(),
+            Err(e) => vec![format!("ERROR - {}", error_reason(&e))],
+        }
+    }
+
+    fn explain(&self, address: &str, signature: &str) -> Vec<String> {
+        let checked = match self.address(address) {
+            Ok(checked) => checked,
+            Err(reason) => return vec![format!("ERROR - {}: {}", address, reason)],
+        };
+        match recover_pubkey(&self.message, signature) {
+            Ok(pubkey) => {
+                let signer = address_like(&pubkey, &checked);
+                if signer == checked {
+                    vec![format!("signed by {} itself", address)]
+                } else {
+                    vec![
+                        format!("SIGNER {} - {}", signer, address),
+                        "signed by another key, or for another message".to_string(),
+                    ]
+                }
+            }
+            Err(e) => vec![format!("ERROR - {}", error_reason(&e))],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m";
+    const SIGNATURE: &str =
+        "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+
+    #[test]
+    fn check_repl() {
+        let mut repl = Repl::new(MESSAGE, Network::Bitcoin);
+        assert_eq!(
+            repl.handle(&format!("{} {}", ADDRESS, SIGNATURE)).unwrap(),
+            [format!("OK - {}", ADDRESS)]
+        );
+
+        // An address alone asks for the rest.
+        assert!(repl.handle(ADDRESS).unwrap().is_empty());
+        assert_eq!(repl.prompt(), "signature> ");
+        assert!(repl.handle(SIGNATURE).unwrap().is_empty());
+        assert!(repl.prompt().starts_with("message"));
+        assert!(repl.handle("We are all Satoshi").unwrap()[0].starts_with("BAD - "));
+        assert_eq!(repl.prompt(), "cwins> ");
+
+        repl.handle(":message We are all Satoshi").unwrap();
+        let explained = repl.handle(&format!(":explain {} {}", ADDRESS, SIGNATURE));
+        assert!(explained.unwrap()[0].starts_with("SIGNER 1"));
+        repl.handle(":message default").unwrap();
+        let explained = repl.handle(&format!(":explain {} {}", ADDRESS, SIGNATURE));
+        assert_eq!(
+            explained.unwrap()[0],
+            format!("signed by {} itself", ADDRESS)
+        );
+
+        let recovered = repl.handle(&format!(":recover {}", SIGNATURE)).unwrap();
+        assert!(recovered[0].starts_with("pubkey 04e5d980"));
+        assert_eq!(recovered[1], format!("address {}", ADDRESS));
+
+        assert!(repl.handle("1Fb abc").unwrap()[0].starts_with("ERROR - 1Fb: "));
+        assert!(repl.handle(":frobnicate").unwrap()[0].starts_with("unknown command"));
+        assert!(repl.handle(":quit").is_none());
+    }
+}
@@ -0,0 +1,141 @@
+// Resuming from an earlier report (`--resume PRIOR_REPORT`): the lines whose address
+// and signature already have a result there take it over instead of being verified
+// again, so re-running over a list that only grew verifies just the new lines. The
+// report is the `--output-format json` or `ndjson` output of the earlier run, whose
+// rows also record the message and the options they were verified with: a result of
+// another message or other options isn't taken over.
+
+use crate::compare::parse_rows;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The result of a line in the earlier report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Prior {
+    /// `valid`, `invalid` or `error`.
+    pub result: String,
+    /// The error code of an `error` result.
+    pub error: Option<String>,
+    pub pubkey: Option<String>,
+    /// SHA-256 of the message bytes verified.
+    pub message: Option<String>,
+    /// The fingerprint of the options that change verdicts.
+    pub options: Option<String>,
+}
+
+impl Prior {
+    /// The verdict, `Err` with the error code.
+    pub fn verdict(&self) -> Result<bool, &str> {
+        match self.result.as_str() {
+            "valid" => Ok(true),
+            "invalid" => Ok(false),
+            _ => Err(self.error.as_deref().unwrap_or("error")),
+        }
+    }
+}
+
+/// The results of an earlier report by address and signature.
+#[derive(Debug, Default)]
+pub struct Resumed {
+    results: HashMap<String, HashMap<String, Vec<Prior>>>,
+}
+
+impl Resumed {
+    /// Reads the result rows of a JSON report or NDJSON lines.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut results: HashMap<String, HashMap<String, Vec<Prior>>> = HashMap::new();
+        for (n, row) in parse_rows(text)?.iter().enumerate() {
+            let field = |name: &str| row.get(name).and_then(Value::as_str).map(str::to_string);
+            let (Some(address), Some(signature), Some(result)) =
+                (field("address"), field("signature"), field("result"))
+            else {
+                return Err(format!("result {} isn't a result row", n + 1));
+            };
+            let prior = Prior {
+                result,
+                error: field("error"),
+                pubkey: field("pubkey"),
+                message: field("message_sha256"),
+                options: field("options_sha256"),
+            };
+            results
+                .entry(address)
+                .or_default()
+                .entry(signature)
+                .or_default()
+                .push(prior);
+        }
+
+        Ok(Resumed { results })
+    }
+
+    /// The earlier result of `signature` by `address` over the message of SHA-256
+    /// `message`, verified with the options of fingerprint `options`.
+    pub fn get(
+        &self,
+        address: &str,
+        signature: &str,
+        message: &str,
+        options: &str,
+    ) -> Option<&Prior> {
+        self.results
+            .get(address)?
+            .get(signature)?
+            .iter()
+            .find(|prior| {
+                prior.message.as_deref() == Some(message)
+                    && prior.options.as_deref() == Some(options)
+            })
+    }
+
+    /// Whether there is an earlier result of `signature` by `address` at all.
+    pub fn listed(&self, address: &str, signature: &str) -> bool {
+        self.results
+            .get(address)
+            .is_some_and(|results| results.contains_key(signature))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_resumed() {
+        let report = r#"{"schema_version":1,"results":[
+            {"address":"1A","signature":"s1","result":"valid","error":null,"pubkey":"02ab","message_sha256":"m1","options_sha256":"o1"},
+            {"address":"1A","signature":"s2","result":"error","error":"signature_base64","pubkey":null,"message_sha256":"m1","options_sha256":"o1"},
+            {"address":"1B","signature":"s3","result":"invalid","error":null,"pubkey":"03cd","message_sha256":"m1","options_sha256":"o1"},
+            {"address":"1B","signature":"s3","result":"valid","error":null,"pubkey":"03cd","message_sha256":"m2","options_sha256":"o1"}
+        ]}"#;
+        let resumed = Resumed::parse(report).unwrap();
+        let get = |address, signature| resumed.get(address, signature, "m1", "o1");
+        assert_eq!(get("1A", "s1").unwrap().verdict(), Ok(true));
+        assert_eq!(get("1A", "s1").unwrap().pubkey.as_deref(), Some("02ab"));
+        assert_eq!(get("1A", "s2").unwrap().verdict(), Err("signature_base64"));
+        assert_eq!(get("1B", "s3").unwrap().verdict(), Ok(false));
+        assert_eq!(
+            resumed.get("1B", "s3", "m2", "o1").unwrap().verdict(),
+            Ok(true)
+        );
+        // Another signature of a known address is a new line.
+        assert_eq!(get("1B", "s1"), None);
+        assert!(!resumed.listed("1B", "s1"));
+        // And so is a known one of another message or with other options.
+        assert_eq!(resumed.get("1A", "s1", "m2", "o1"), None);
+        assert_eq!(resumed.get("1A", "s1", "m1", "o2"), None);
+        assert!(resumed.listed("1A", "s1"));
+
+        // A report without the message and options has nothing to take over.
+        let ndjson = "{\"address\":\"1A\",\"signature\":\"s1\",\"result\":\"valid\"}\n";
+        let resumed = Resumed::parse(ndjson).unwrap();
+        assert!(resumed.listed("1A", "s1"));
+        assert!(resumed.get("1A", "s1", "m1", "o1").is_none());
+        assert!(Resumed::parse("{\"results\":[{\"address\":\"1A\"}]}").is_err());
+        assert!(Resumed::parse("").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+use crate::signature::{decode_signature_into, secp, signed_msg_hash, SIGNATURE_LEN};
+use crate::VerifyError;
+use bitcoin::address::NetworkChecked;
+use bitcoin::secp256k1::{schnorr, Message, XOnlyPublicKey};
+use bitcoin::sign_message::MessageSignatureError;
+use bitcoin::{Address, AddressType};
+
+/// The x-only output key of a P2TR address, the key a key path signer holds
+/// (after the taproot tweak).
+pub fn taproot_output_key(address: &Address<NetworkChecked>) -> Option<XOnlyPublicKey> {
+    if address.address_type() != Some(AddressType::P2tr) {
+        return None;
+    }
+
+    XOnlyPublicKey::from_slice(&address.script_pubkey().as_bytes()[2..]).ok()
+}
+
+/// Verifies a base64 64-byte BIP-340 signature over the BIP-137 signed-message hash.
+pub fn check_schnorr_sig(
+    pubkey: &XOnlyPublicKey,
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<bool, VerifyError> {
+    let mut buf = [0; SIGNATURE_LEN];
+    let bytes = decode_signature_into(signature, &mut buf)?;
+
+    check_schnorr_sig_bytes(pubkey, message, &bytes)
+}
+
+pub fn check_schnorr_sig_bytes(
+    pubkey: &XOnlyPublicKey,
+    message: impl AsRef<[u8]>,
+    signature: &[u8],
+) -> Result<bool, VerifyError> {
+    let secp = secp();
+
+    let signature = schnorr::Signature::from_slice(signature)
+        .map_err(MessageSignatureError::InvalidEncoding)?;
+    let msg = Message::from_slice(&signed_msg_hash(message)[..])
+        .map_err(MessageSignatureError::InvalidEncoding)?;
+
+    Ok(secp.verify_schnorr(&signature, &msg, pubkey).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_sig, MESSAGE};
+    use base64::Engine;
+    use bitcoin::key::TapTweak;
+    use bitcoin::secp256k1::KeyPair;
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::Network;
+
+    #[test]
+    fn check_schnorr_signatures() {
+        let secp = Secp256k1::new();
+        let keypair = KeyPair::from_seckey_slice(&secp, &[3u8; 32]).unwrap();
+        let (internal_key, _) = keypair.x_only_public_key();
+
+        let address = Address::p2tr(&secp, internal_key, None, Network::Bitcoin);
+        let output_key = taproot_output_key(&address).unwrap();
+        let tweaked = keypair.tap_tweak(&secp, None).to_inner();
+        assert_eq!(tweaked.x_only_public_key().0, output_key);
+
+        let msg = Message::from_slice(&signed_msg_hash(MESSAGE)[..]).unwrap();
+        let encode = |sig: schnorr::Signature| {
+            base64::engine::general_purpose::STANDARD.encode(sig.as_ref())
+        };
+        let by_output_key = encode(secp.sign_schnorr_no_aux_rand(&msg, &tweaked));
+        let by_internal_key = encode(secp.sign_schnorr_no_aux_rand(&msg, &keypair));
+
+        assert_eq!(
+            check_schnorr_sig(&internal_key, MESSAGE, &by_internal_key).ok(),
+            Some(true)
+        );
+        assert_eq!(
+            check_schnorr_sig(&internal_key, "We are all Satoshi", &by_internal_key).ok(),
+            Some(false)
+        );
+
+        assert_eq!(
+            check_sig(address.clone(), MESSAGE, &by_output_key).ok(),
+            Some(true)
+        );
+        assert_eq!(
+            check_sig(address, MESSAGE, &by_internal_key).ok(),
+            Some(false)
+        );
+
+        assert!(taproot_output_key(
+            &"1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"
+                .parse::<Address<_>>()
+                .unwrap()
+                .require_network(Network::Bitcoin)
+                .unwrap()
+        )
+        .is_none());
+    }
+}
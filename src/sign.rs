@@ -0,0 +1,101 @@
+use crate::signature::signed_msg_hash;
+use base64::Engine;
+use bitcoin::address::{self, NetworkChecked};
+use bitcoin::secp256k1::{Message, Secp256k1};
+use bitcoin::{Address, AddressType, PrivateKey};
+
+/// First BIP-137 header byte of the address type, the recovery id is added to it.
+fn header_base(address_type: AddressType, compressed: bool) -> u8 {
+    match (address_type, compressed) {
+        (AddressType::P2pkh, false) => 27,
+        (AddressType::P2sh, _) => 35,
+        (AddressType::P2wpkh, _) => 39,
+        _ => 31,
+    }
+}
+
+/// Signs `message` with `key` and returns the signing address of `address_type`
+/// (P2PKH, P2SH-P2WPKH or P2WPKH) together with the base64 BIP-137 signature.
+///
+/// Segwit addresses need a compressed key, other address types aren't supported.
+pub fn sign_message(
+    key: &PrivateKey,
+    message: impl AsRef<[u8]>,
+    address_type: AddressType,
+) -> Result<(Address<NetworkChecked>, String), address::Error> {
+    let secp = Secp256k1::new();
+    let pubkey = key.public_key(&secp);
+
+    let address = match address_type {
+        AddressType::P2pkh => Address::p2pkh(&pubkey, key.network),
+        AddressType::P2sh => Address::p2shwpkh(&pubkey, key.network)?,
+        AddressType::P2wpkh => Address::p2wpkh(&pubkey, key.network)?,
+        _ => return Err(address::Error::UnknownAddressType(address_type.to_string())),
+    };
+
+    let msg = Message::from_slice(&signed_msg_hash(message)[..])
+        .expect("signed_msg_hash is 32 bytes long");
+    let (recid, compact) = secp
+        .sign_ecdsa_recoverable(&msg, &key.inner)
+        .serialize_compact();
+
+    let mut bytes = Vec::with_capacity(65);
+    bytes.push(header_base(address_type, key.compressed) + recid.to_i32() as u8);
+    bytes.extend_from_slice(&compact);
+
+    Ok((
+        address,
+        base64::engine::general_purpose::STANDARD.encode(bytes),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::parse_signature;
+    use crate::{check_sig, MESSAGE};
+    use bitcoin::Network;
+
+    #[test]
+    fn check_sign_message() {
+        let compressed = PrivateKey::from_slice(&[5u8; 32], Network::Bitcoin).unwrap();
+        let uncompressed = PrivateKey {
+            compressed: false,
+            ..compressed
+        };
+
+        let checks = [
+            (&uncompressed, AddressType::P2pkh, 27..=30),
+            (&compressed, AddressType::P2pkh, 31..=34),
+            (&compressed, AddressType::P2sh, 35..=38),
+            (&compressed, AddressType::P2wpkh, 39..=42),
+        ];
+
+        for (key, address_type, headers) in checks {
+            let (address, signature) = sign_message(key, MESSAGE, address_type).unwrap();
+
+            assert_eq!(address.address_type(), Some(address_type));
+            let header = base64::engine::general_purpose::STANDARD
+                .decode(&signature)
+                .unwrap()[0];
+            assert!(headers.contains(&header));
+            assert!(parse_signature(&signature).is_ok());
+            assert_eq!(check_sig(address, MESSAGE, &signature).ok(), Some(true));
+        }
+
+        // Bytes that aren't UTF-8 are signed as they are.
+        let (address, signature) =
+            sign_message(&compressed, b"\xff\n", AddressType::P2wpkh).unwrap();
+        assert_eq!(
+            check_sig(address.clone(), b"\xff\n", &signature).ok(),
+            Some(true)
+        );
+        assert_eq!(
+            check_sig(address, "\u{fffd}\n", &signature).ok(),
+            Some(false)
+        );
+
+        assert!(sign_message(&uncompressed, MESSAGE, AddressType::P2wpkh).is_err());
+        assert!(sign_message(&compressed, MESSAGE, AddressType::P2tr).is_err());
+    }
+}
@@ -0,0 +1,343 @@
+use crate::VerifyError;
+use base64::{DecodeSliceError, Engine};
+use bitcoin::consensus::encode::{Encodable, VarInt};
+use bitcoin::hashes::hex::FromHex;
+use bitcoin::hashes::{sha256d, Hash, HashEngine};
+use bitcoin::secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use bitcoin::secp256k1::{Secp256k1, VerifyOnly};
+use bitcoin::sign_message::{MessageSignature, MessageSignatureError, BITCOIN_SIGNED_MSG_PREFIX};
+use bitcoin::PublicKey;
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Size of a BIP-137 signature, the longest one [`decode_signature_into`] decodes
+/// without allocating.
+pub const SIGNATURE_LEN: usize = 65;
+
+/// Splits a BIP-137 header byte into the recovery id and the compression flag:
+///
+/// - 27-30: P2PKH, uncompressed key
+/// - 31-34: P2PKH, compressed key
+/// - 35-38: P2SH-P2WPKH (compressed key)
+/// - 39-42: P2WPKH (compressed key)
+///
+/// `MessageSignature::from_slice` only masks the low bits, so e.g. 35 would be read as
+/// an uncompressed key and 255 wouldn't be rejected at all.
+pub fn decode_header(header: u8) -> Result<(RecoveryId, bool), VerifyError> {
+    let (recid, compressed) = match header {
+        27..=30 => (header - 27, false),
+        31..=42 => ((header - 27) % 4, true),
+        _ => return Err(VerifyError::InvalidHeader(header)),
+    };
+
+    let recid = RecoveryId::from_i32(recid as i32)
+        .map_err(|e| VerifyError::GeneralSignatureProblem(MessageSignatureError::from(e)))?;
+
+    Ok((recid, compressed))
+}
+
+/// Decodes a 65-byte signature (header byte and compact `r || s`) with a validated header.
+pub fn parse_signature_bytes(bytes: &[u8]) -> Result<MessageSignature, VerifyError> {
+    if bytes.len() != 65 {
+        return Err(MessageSignatureError::InvalidLength.into());
+    }
+
+    let (recid, compressed) = decode_header(bytes[0])?;
+    let signature = RecoverableSignature::from_compact(&bytes[1..], recid)
+        .map_err(MessageSignatureError::from)?;
+
+    Ok(MessageSignature::new(signature, compressed))
+}
+
+/// Decodes a signature given as standard base64, URL-safe base64 (padding optional
+/// for both) or hex. Hex is only assumed for strings of at least 64 bytes that
+/// consist of hex digits alone, a base64 65-byte signature is never one.
+pub fn decode_signature(signature: &str) -> Result<Vec<u8>, VerifyError> {
+    if signature.len() >= 128 && signature.bytes().all(|b| b.is_ascii_hexdigit()) {
+        if let Ok(bytes) = Vec::from_hex(signature) {
+            return Ok(bytes);
+        }
+    }
+
+    let engine = if signature.contains(['-', '_']) {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+    } else {
+        base64::engine::general_purpose::STANDARD_NO_PAD
+    };
+
+    Ok(engine.decode(signature.trim_end_matches('='))?)
+}
+
+/// [`decode_signature`] into `buf`: signatures of up to 65 bytes (BIP-137 and
+/// BIP-340 ones) borrow it, only longer ones (BIP-322 proofs) are allocated.
+pub fn decode_signature_into<'a>(
+    signature: &str,
+    buf: &'a mut [u8; SIGNATURE_LEN],
+) -> Result<Cow<'a, [u8]>, VerifyError> {
+    if signature.len() >= 128
+        && signature.len() <= 2 * SIGNATURE_LEN
+        && signature.len().is_multiple_of(2)
+        && signature.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        let digit = |b: u8| (b as char).to_digit(16).expect("checked above") as u8;
+        let len = signature.len() / 2;
+        for (byte, pair) in buf.iter_mut().zip(signature.as_bytes().chunks_exact(2)) {
+            *byte = digit(pair[0]) << 4 | digit(pair[1]);
+        }
+        return Ok(Cow::Borrowed(&buf[..len]));
+    }
+
+    let engine = if signature.contains(['-', '_']) {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+    } else {
+        base64::engine::general_purpose::STANDARD_NO_PAD
+    };
+    let trimmed = signature.trim_end_matches('=');
+    if trimmed.len() * 3 / 4 <= SIGNATURE_LEN {
+        match engine.decode_slice(trimmed, &mut buf[..]) {
+            Ok(len) => return Ok(Cow::Borrowed(&buf[..len])),
+            Err(DecodeSliceError::DecodeError(e)) => return Err(e.into()),
+            // Decoded below after all.
+            Err(DecodeSliceError::OutputSliceTooSmall) => {}
+        }
+    }
+
+    decode_signature(signature).map(Cow::Owned)
+}
+
+/// Half the order of the secp256k1 group, the largest low `s` value.
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// The fields of a 65 byte BIP-137 signature, printed by `--dump-sig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureParts {
+    pub header: u8,
+    /// The recovery id and compression flag, `None` for a header out of range.
+    pub decoded: Option<(i32, bool)>,
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    /// Whether `s` is in the lower half of the group order, as standard signers
+    /// (BIP-62 and later) make them.
+    pub low_s: bool,
+}
+
+impl fmt::Display for SignatureParts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hex = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        };
+        match self.decoded {
+            Some((recid, compressed)) => {
+                let key = if compressed {
+                    "compressed"
+                } else {
+                    "uncompressed"
+                };
+                write!(f, "header {} recid {} {}", self.header, recid, key)?
+            }
+            None => write!(f, "header {} out of range", self.header)?,
+        }
+        let s = if self.low_s { "low" } else { "high" };
+        write!(f, " r {} s {} ({} s)", hex(&self.r), hex(&self.s), s)
+    }
+}
+
+/// Splits a 65 byte BIP-137 `signature` into its fields without checking them
+/// beyond the length.
+pub fn decompose(signature: &str) -> Result<SignatureParts, VerifyError> {
+    let mut buf = [0; SIGNATURE_LEN];
+    let bytes = decode_signature_into(signature, &mut buf)?;
+    if bytes.len() != SIGNATURE_LEN {
+        return Err(MessageSignatureError::InvalidLength.into());
+    }
+
+    let s: [u8; 32] = bytes[33..].try_into().expect("32 bytes");
+    Ok(SignatureParts {
+        header: bytes[0],
+        decoded: decode_header(bytes[0])
+            .ok()
+            .map(|(recid, compressed)| (recid.to_i32(), compressed)),
+        r: bytes[1..33].try_into().expect("32 bytes"),
+        s,
+        low_s: s <= HALF_ORDER,
+    })
+}
+
+pub fn parse_signature(signature: &str) -> Result<MessageSignature, VerifyError> {
+    let mut buf = [0; SIGNATURE_LEN];
+    let bytes = decode_signature_into(signature, &mut buf)?;
+
+    parse_signature_bytes(&bytes)
+}
+
+/// The verification context shared by all the checks of the process, made on first
+/// use instead of for every signature.
+pub fn secp() -> &'static Secp256k1<VerifyOnly> {
+    static SECP: OnceLock<Secp256k1<VerifyOnly>> = OnceLock::new();
+    SECP.get_or_init(Secp256k1::verification_only)
+}
+
+/// The signed-message hash of any bytes, not only of text like
+/// [`bitcoin::sign_message::signed_msg_hash`]: the double SHA-256 of the prefix, the
+/// length and the message.
+pub fn signed_msg_hash(message: impl AsRef<[u8]>) -> sha256d::Hash {
+    let message = message.as_ref();
+    let mut engine = sha256d::Hash::engine();
+    engine.input(BITCOIN_SIGNED_MSG_PREFIX);
+    VarInt(message.len() as u64)
+        .consensus_encode(&mut engine)
+        .expect("hash engines don't fail");
+    engine.input(message);
+    sha256d::Hash::from_engine(engine)
+}
+
+pub fn recover_pubkey(
+    message: impl AsRef<[u8]>,
+    signature: &str,
+) -> Result<PublicKey, VerifyError> {
+    let sss = parse_signature(signature)?;
+    let msg_hash = signed_msg_hash(message);
+
+    Ok(sss.recover_pubkey(secp(), msg_hash)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error_reason, MESSAGE};
+
+    #[test]
+    fn check_signed_msg_hash() {
+        assert_eq!(
+            signed_msg_hash(MESSAGE),
+            bitcoin::sign_message::signed_msg_hash(MESSAGE)
+        );
+        // Bytes that aren't UTF-8 hash like any other.
+        assert_eq!(
+            signed_msg_hash(b"\xff\xfe").to_byte_array(),
+            sha256d::Hash::hash(b"\x18Bitcoin Signed Message:\n\x02\xff\xfe").to_byte_array()
+        );
+    }
+
+    #[test]
+    fn check_header_ranges() {
+        for header in 27..=42u8 {
+            let (recid, compressed) = decode_header(header).unwrap();
+            assert_eq!(recid.to_i32(), ((header - 27) % 4) as i32);
+            assert_eq!(compressed, header >= 31);
+        }
+
+        for header in [0, 26, 43, 255] {
+            assert!(matches!(
+                decode_header(header),
+                Err(VerifyError::InvalidHeader(h)) if h == header
+            ));
+        }
+
+        // A valid signature with its header pushed out of range.
+        let mut bytes = parse_signature("G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=")
+            .unwrap()
+            .serialize();
+        assert_eq!(bytes[0], 27);
+        bytes[0] = 27 + 16;
+        assert!(matches!(
+            parse_signature_bytes(&bytes),
+            Err(VerifyError::InvalidHeader(43))
+        ));
+    }
+
+    #[test]
+    fn check_error_reasons() {
+        let reason = |bytes: &[u8]| {
+            let signature = base64::engine::general_purpose::STANDARD.encode(bytes);
+            match recover_pubkey(MESSAGE, &signature) {
+                Err(VerifyError::GeneralSignatureProblem(e)) => error_reason(&e),
+                r => panic!("unexpected result: {:?}", r),
+            }
+        };
+
+        let mut bytes = [0xffu8; 65];
+        bytes[0] = 27;
+        assert_eq!(reason(&bytes), "invalid encoding: malformed signature");
+        assert_eq!(reason(&bytes[..64]), "length not 65 bytes");
+
+        assert!(matches!(
+            recover_pubkey(MESSAGE, "not base64!"),
+            Err(VerifyError::SignatureBase64DecodeError(_))
+        ));
+    }
+
+    #[test]
+    fn check_signature_encodings() {
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+        let bytes = decode_signature(signature).unwrap();
+        assert_eq!(bytes.len(), 65);
+
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let url_safe = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(&bytes);
+        assert!(url_safe.contains(['-', '_']));
+        for encoded in [
+            hex.clone(),
+            hex.to_uppercase(),
+            url_safe,
+            signature.trim_end_matches('=').to_string(),
+        ] {
+            assert_eq!(decode_signature(&encoded).unwrap(), bytes);
+            let mut buf = [0; SIGNATURE_LEN];
+            let decoded = decode_signature_into(&encoded, &mut buf).unwrap();
+            assert!(matches!(decoded, Cow::Borrowed(b) if b == bytes));
+        }
+
+        assert!(matches!(
+            decode_signature(&hex[1..]),
+            Err(VerifyError::SignatureBase64DecodeError(_))
+        ));
+        let mut buf = [0; SIGNATURE_LEN];
+        assert!(matches!(
+            decode_signature_into(&hex[1..], &mut buf),
+            Err(VerifyError::SignatureBase64DecodeError(_))
+        ));
+
+        // Schnorr signatures fit the buffer, longer BIP-322 proofs don't.
+        let schnorr = base64::engine::general_purpose::STANDARD.encode([7u8; 64]);
+        let decoded = decode_signature_into(&schnorr, &mut buf).unwrap();
+        assert!(matches!(decoded, Cow::Borrowed(b) if b == [7u8; 64]));
+        let proof = base64::engine::general_purpose::STANDARD.encode([7u8; 107]);
+        let decoded = decode_signature_into(&proof, &mut buf).unwrap();
+        assert!(matches!(decoded, Cow::Owned(b) if b == [7u8; 107]));
+    }
+
+    #[test]
+    fn check_decompose() {
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+        let parts = decompose(signature).unwrap();
+        assert_eq!(
+            (parts.header, parts.decoded, parts.low_s),
+            (27, Some((0, false)), true)
+        );
+        assert!(parts
+            .to_string()
+            .starts_with("header 27 recid 0 uncompressed r 74ac80a3"));
+        assert!(parts.to_string().ends_with("f844f562 (low s)"));
+
+        // The same signature with s replaced by n - s, and a header out of range.
+        let mut bytes = decode_signature(signature).unwrap();
+        let high = bitcoin::secp256k1::SecretKey::from_slice(&bytes[33..])
+            .unwrap()
+            .negate();
+        bytes[33..].copy_from_slice(&high.secret_bytes());
+        bytes[0] = 43;
+        let parts = decompose(&base64::engine::general_purpose::STANDARD.encode(&bytes)).unwrap();
+        assert!(!parts.low_s);
+        assert!(parts.to_string().starts_with("header 43 out of range r "));
+
+        assert!(decompose(&"ab".repeat(64)).is_err());
+    }
+}
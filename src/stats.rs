@@ -0,0 +1,136 @@
+// Statistics over many runs (`stats results/*.ndjson`) for keeping an eye on the list
+// as it evolves: the totals of every run, the addresses that appeared later on and
+// the ones whose verdict keeps changing. The runs are given oldest first and read
+// like `diff` reads them.
+
+use crate::compare::Run;
+use std::collections::BTreeMap;
+
+/// The verdicts of an address in each run, `None` where it isn't listed.
+pub type History = Vec<Option<String>>;
+
+/// The counts of one run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    pub addresses: usize,
+    pub valid: usize,
+    pub invalid: usize,
+    pub errors: usize,
+    /// Addresses no earlier run listed.
+    pub new: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub totals: Vec<Totals>,
+    pub histories: BTreeMap<String, History>,
+}
+
+impl Stats {
+    pub fn new(runs: &[Run]) -> Self {
+        let mut stats = Stats::default();
+        for (i, run) in runs.iter().enumerate() {
+            let mut totals = Totals {
+                addresses: run.len(),
+                ..Totals::default()
+            };
+            for (address, entry) in run {
+                match entry.result.as_str() {
+                    "valid" => totals.valid += 1,
+                    "invalid" => totals.invalid += 1,
+                    _ => totals.errors += 1,
+                }
+                let history = stats.histories.entry(address.clone()).or_insert_with(|| {
+                    totals.new += 1;
+                    vec![None; runs.len()]
+                });
+                history[i] = Some(entry.result.clone());
+            }
+            stats.totals.push(totals);
+        }
+
+        stats
+    }
+
+    /// The addresses missing from the first run, with the index of the run that
+    /// listed them first.
+    pub fn new_addresses(&self) -> Vec<(&str, usize)> {
+        self.histories
+            .iter()
+            .filter_map(|(address, history)| {
+                let first = history.iter().position(Option::is_some)?;
+                (first > 0).then_some((address.as_str(), first))
+            })
+            .collect()
+    }
+
+    /// The addresses whose verdict changed more than once, e.g. valid, invalid and
+    /// valid again.
+    pub fn flip_flops(&self) -> Vec<(&str, &History)> {
+        self.histories
+            .iter()
+            .filter(|(_, history)| changes(history) > 1)
+            .map(|(address, history)| (address.as_str(), history))
+            .collect()
+    }
+}
+
+/// How many times the verdict changed between the runs listing the address.
+pub fn changes(history: &History) -> usize {
+    let verdicts: Vec<&String> = history.iter().flatten().collect();
+    verdicts.windows(2).filter(|w| w[0] != w[1]).count()
+}
+
+/// `valid, -, invalid`, with `-` for the runs not listing the address.
+pub fn format_history(history: &History) -> String {
+    history
+        .iter()
+        .map(|verdict| verdict.as_deref().unwrap_or("-"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compare::parse_run;
+
+    #[test]
+    fn check_stats() {
+        let row = |address: &str, result: &str| {
+            format!(
+                r#"{{"address":"{}","signature":"s","result":"{}"}}"#,
+                address, result
+            )
+        };
+        let runs: Vec<Run> = [
+            [row("1A", "valid"), row("1B", "valid")].join("\n"),
+            [row("1A", "invalid"), row("1C", "error")].join("\n"),
+            [row("1A", "valid"), row("1B", "valid"), row("1C", "valid")].join("\n"),
+        ]
+        .iter()
+        .map(|text| parse_run(text).unwrap())
+        .collect();
+
+        let stats = Stats::new(&runs);
+        assert_eq!(
+            stats.totals[1],
+            Totals {
+                addresses: 2,
+                valid: 0,
+                invalid: 1,
+                errors: 1,
+                new: 1
+            }
+        );
+        assert_eq!(stats.totals[0].new, 2);
+        assert_eq!(stats.new_addresses(), [("1C", 1)]);
+
+        let flip_flops = stats.flip_flops();
+        assert_eq!(flip_flops.len(), 1);
+        assert_eq!(flip_flops[0].0, "1A");
+        assert_eq!(format_history(flip_flops[0].1), "valid, invalid, valid");
+        assert_eq!(format_history(&stats.histories["1B"]), "valid, -, valid");
+        assert_eq!(changes(&stats.histories["1C"]), 1);
+    }
+}
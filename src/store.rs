@@ -0,0 +1,199 @@
+// SQLite storage of the results (`--store sqlite:results.db`). Every run gets a row in
+// `runs` and every verified line one in `results`, keyed by the address and the
+// hashes of the signature and the message, so later runs can reuse the verdicts and
+// the history of several campaigns can be queried with plain SQL.
+
+use crate::signature::signed_msg_hash;
+use bitcoin::hashes::{sha256, Hash};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where the results are stored, `sqlite:<path>` on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreLocation {
+    Sqlite(PathBuf),
+}
+
+impl FromStr for StoreLocation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some(("sqlite", path)) if !path.is_empty() => Ok(StoreLocation::Sqlite(path.into())),
+            _ => Err(format!("unknown store `{}`, expected sqlite:<path>", s)),
+        }
+    }
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started INTEGER NOT NULL,
+    version TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    address TEXT NOT NULL,
+    signature_hash TEXT NOT NULL,
+    message_hash TEXT NOT NULL,
+    verdict TEXT NOT NULL,
+    error TEXT,
+    checked INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS results_line ON results (address, signature_hash, message_hash);
+";
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+fn hashes(signature: &str, message: &[u8]) -> (String, String) {
+    (
+        sha256::Hash::hash(signature.as_bytes()).to_string(),
+        signed_msg_hash(message).to_string(),
+    )
+}
+
+/// A database opened for one run. The results are written in a transaction that is
+/// committed by [`Store::commit`].
+pub struct Store {
+    connection: Connection,
+    run: i64,
+}
+
+impl Store {
+    /// Opens (or creates) the database at `path` and starts a new run.
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        connection.execute(
+            "INSERT INTO runs (started, version) VALUES (?1, ?2)",
+            params![now(), env!("CARGO_PKG_VERSION")],
+        )?;
+        let run = connection.last_insert_rowid();
+        connection.execute_batch("BEGIN")?;
+
+        Ok(Store { connection, run })
+    }
+
+    /// The id of this run in `runs`.
+    pub fn run(&self) -> i64 {
+        self.run
+    }
+
+    /// Writes the result of one line, `Err` with an error code.
+    pub fn record(
+        &self,
+        address: &str,
+        signature: &str,
+        message: impl AsRef<[u8]>,
+        result: Result<bool, &str>,
+    ) -> rusqlite::Result<()> {
+        let (signature_hash, message_hash) = hashes(signature, message.as_ref());
+        let verdict = match result {
+            Ok(true) => "valid",
+            Ok(false) => "invalid",
+            Err(_) => "error",
+        };
+
+        self.connection.execute(
+            "INSERT INTO results (run, address, signature_hash, message_hash, verdict, error, checked)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                self.run,
+                address,
+                signature_hash,
+                message_hash,
+                verdict,
+                result.err(),
+                now()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The latest verdict any run recorded for the line, errors aren't reused.
+    pub fn verdict(
+        &self,
+        address: &str,
+        signature: &str,
+        message: impl AsRef<[u8]>,
+    ) -> rusqlite::Result<Option<bool>> {
+        let (signature_hash, message_hash) = hashes(signature, message.as_ref());
+        let verdict: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT verdict FROM results
+                 WHERE address = ?1 AND signature_hash = ?2 AND message_hash = ?3
+                   AND verdict != 'error'
+                 ORDER BY run DESC, rowid DESC LIMIT 1",
+                params![address, signature_hash, message_hash],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(verdict.map(|v| v == "valid"))
+    }
+
+    /// Commits the results written so far.
+    pub fn commit(&self) -> rusqlite::Result<()> {
+        self.connection.execute_batch("COMMIT; BEGIN")
+    }
+}
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        let _ = self.connection.execute_batch("COMMIT");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MESSAGE;
+    use std::fs;
+
+    #[test]
+    fn check_sqlite_store() {
+        let path = std::env::temp_dir().join("cwins-check-store-test.db");
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            "sqlite:results.db".parse(),
+            Ok(StoreLocation::Sqlite("results.db".into()))
+        );
+        assert!("postgres://host".parse::<StoreLocation>().is_err());
+
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.run(), 1);
+        store.record("1Fb", "G3Ss", MESSAGE, Ok(true)).unwrap();
+        store.record("1Bp", "HFjd", MESSAGE, Ok(false)).unwrap();
+        store
+            .record("1xx", "bad", MESSAGE, Err("address_parse"))
+            .unwrap();
+        store.commit().unwrap();
+        assert_eq!(store.verdict("1Fb", "G3Ss", MESSAGE).unwrap(), Some(true));
+        drop(store);
+
+        let store = Store::open(&path).unwrap();
+        assert_eq!(store.run(), 2);
+        assert_eq!(store.verdict("1Fb", "G3Ss", MESSAGE).unwrap(), Some(true));
+        assert_eq!(store.verdict("1Bp", "HFjd", MESSAGE).unwrap(), Some(false));
+        assert_eq!(store.verdict("1Fb", "G3Ss", "other").unwrap(), None);
+        assert_eq!(store.verdict("1xx", "bad", MESSAGE).unwrap(), None);
+
+        store.record("1Bp", "HFjd", MESSAGE, Ok(true)).unwrap();
+        assert_eq!(store.verdict("1Bp", "HFjd", MESSAGE).unwrap(), Some(true));
+        drop(store);
+
+        let connection = Connection::open(&path).unwrap();
+        let rows: i64 = connection
+            .query_row("SELECT COUNT(*) FROM results", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(rows, 4);
+        fs::remove_file(&path).unwrap();
+    }
+}
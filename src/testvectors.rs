@@ -0,0 +1,132 @@
+// Test vectors (`gen-testvectors`) for checking this tool, wallets and other verifiers
+// against each other: keys derived from a seed sign the message for every address
+// type, and each good signature comes with deliberately broken copies. The vectors
+// are `<address> <signature> <label> <expected>` lines, read with `--extra-columns
+// passthrough` every result line shows the verdict next to the expected one.
+
+use crate::sign::sign_message;
+use base64::Engine;
+use bitcoin::address::NetworkChecked;
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::secp256k1::SecretKey;
+use bitcoin::{Address, AddressType, Network, PrivateKey};
+use std::fmt;
+
+/// The address types the keys sign for in turn, with the compression of the key.
+const TYPES: [(&str, AddressType, bool); 4] = [
+    ("p2pkh-uncompressed", AddressType::P2pkh, false),
+    ("p2pkh", AddressType::P2pkh, true),
+    ("p2sh-p2wpkh", AddressType::P2sh, true),
+    ("p2wpkh", AddressType::P2wpkh, true),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestVector {
+    /// `<kind>/<address type>/<key index>`, e.g. `bad-recid/p2wpkh/3`.
+    pub label: String,
+    /// The verdict of a correct verifier, `OK` or `BAD`.
+    pub expected: &'static str,
+    pub address: Address<NetworkChecked>,
+    pub signature: String,
+}
+
+impl fmt::Display for TestVector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.address, self.signature, self.label, self.expected
+        )
+    }
+}
+
+/// The `index`th key of `seed`: the SHA-256 of the seed and the index.
+fn key(seed: &str, index: u64, compressed: bool, network: Network) -> PrivateKey {
+    let mut data = seed.as_bytes().to_vec();
+    data.extend(index.to_le_bytes());
+    let secret =
+        SecretKey::from_slice(&sha256::Hash::hash(&data)[..]).expect("a hash is a valid key");
+    PrivateKey {
+        compressed,
+        ..PrivateKey::new(secret, network)
+    }
+}
+
+/// The signature with its decoded bytes changed by `corrupt`.
+fn corrupted(signature: &str, corrupt: impl FnOnce(&mut Vec<u8>)) -> String {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let mut bytes = engine.decode(signature).expect("a signature just made");
+    corrupt(&mut bytes);
+    engine.encode(bytes)
+}
+
+/// The vectors of `count` keys of `seed` signing `message`, the same ones for the same
+/// arguments: per key a good signature, one with the other recovery id, one with a
+/// flipped byte of `s` and the good one paired with the address of the next key.
+pub fn generate(seed: &str, count: usize, message: &str, network: Network) -> Vec<TestVector> {
+    let signed: Vec<_> = (0..count as u64)
+        .map(|i| {
+            let (name, address_type, compressed) = TYPES[i as usize % TYPES.len()];
+            let key = key(seed, i, compressed, network);
+            let (address, signature) =
+                sign_message(&key, message, address_type).expect("the type can be signed for");
+            (name, address, signature)
+        })
+        .collect();
+
+    let mut vectors = Vec::with_capacity(count * 4);
+    for (i, (name, address, signature)) in signed.iter().enumerate() {
+        let vector =
+            |kind: &str, expected, address: &Address<NetworkChecked>, signature| TestVector {
+                label: format!("{}/{}/{}", kind, name, i),
+                expected,
+                address: address.clone(),
+                signature,
+            };
+        let other = &signed[(i + 1) % signed.len()].1;
+
+        vectors.push(vector("good", "OK", address, signature.clone()));
+        // The other parity of R recovers another key.
+        let recid = corrupted(signature, |bytes| {
+            let id = (bytes[0] - 27) % 4;
+            bytes[0] = bytes[0] - id + (id ^ 1);
+        });
+        vectors.push(vector("bad-recid", "BAD", address, recid));
+        let flipped = corrupted(signature, |bytes| bytes[64] ^= 1);
+        vectors.push(vector("flipped-byte", "BAD", address, flipped));
+        if other != address {
+            vectors.push(vector("wrong-address", "BAD", other, signature.clone()));
+        }
+    }
+
+    vectors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check_sig, MESSAGE};
+
+    #[test]
+    fn check_generate() {
+        let vectors = generate("seed", 5, MESSAGE, Network::Bitcoin);
+        assert_eq!(vectors.len(), 20);
+        assert_eq!(vectors, generate("seed", 5, MESSAGE, Network::Bitcoin));
+        assert_ne!(vectors, generate("other", 5, MESSAGE, Network::Bitcoin));
+
+        for vector in &vectors {
+            let verdict = match check_sig(vector.address.clone(), MESSAGE, &vector.signature) {
+                Ok(true) => "OK",
+                Ok(false) => "BAD",
+                Err(e) => panic!("{}: {}", vector.label, e),
+            };
+            assert_eq!(verdict, vector.expected, "{}", vector.label);
+        }
+        assert_eq!(vectors[8].label, "good/p2sh-p2wpkh/2");
+        assert!(vectors[4].address.to_string().starts_with('1'));
+        assert!(vectors[12].to_string().ends_with(" good/p2wpkh/3 OK"));
+
+        // A single key has no other address to pair with.
+        assert_eq!(generate("seed", 1, MESSAGE, Network::Bitcoin).len(), 3);
+    }
+}
@@ -0,0 +1,301 @@
+// Terminal dashboard of a running verification (`--tui`): running OK/BAD/ERROR counters
+// and throughput, a table of the latest results and a filterable list of the
+// failures. Keys: `/` edits the failure filter (Enter or Esc stops editing, Esc
+// again clears it) and `q` (or Ctrl-C) quits.
+
+use crate::logging;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Results kept for the table, the failures are all kept.
+const RECENT: usize = 500;
+/// Minimum time between two redraws.
+const FRAME: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+struct State {
+    recent: VecDeque<(String, &'static str, String)>,
+    failures: Vec<String>,
+    valid: usize,
+    invalid: usize,
+    errors: usize,
+    started: Instant,
+    filter: String,
+    editing: bool,
+    done: bool,
+}
+
+impl State {
+    fn new() -> Self {
+        State {
+            recent: VecDeque::new(),
+            failures: Vec::new(),
+            valid: 0,
+            invalid: 0,
+            errors: 0,
+            started: Instant::now(),
+            filter: String::new(),
+            editing: false,
+            done: false,
+        }
+    }
+
+    fn push(&mut self, address: &str, result: Result<bool, &str>) {
+        let (verdict, detail) = match result {
+            Ok(true) => {
+                self.valid += 1;
+                ("OK", String::new())
+            }
+            Ok(false) => {
+                self.invalid += 1;
+                self.failures.push(format!("BAD - {}", address));
+                ("BAD", String::new())
+            }
+            Err(code) => {
+                self.errors += 1;
+                self.failures.push(format!("ERROR {} - {}", code, address));
+                ("ERROR", code.to_string())
+            }
+        };
+
+        if self.recent.len() == RECENT {
+            self.recent.pop_front();
+        }
+        self.recent
+            .push_back((address.to_string(), verdict, detail));
+    }
+
+    /// Handles a key press, `false` once the user wants to quit.
+    fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        if code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL) {
+            return false;
+        }
+
+        match (self.editing, code) {
+            (true, KeyCode::Enter | KeyCode::Esc) => self.editing = false,
+            (true, KeyCode::Backspace) => {
+                self.filter.pop();
+            }
+            (true, KeyCode::Char(c)) => self.filter.push(c),
+            (false, KeyCode::Char('q')) => return false,
+            (false, KeyCode::Char('/')) => self.editing = true,
+            (false, KeyCode::Esc) => self.filter.clear(),
+            _ => {}
+        }
+        true
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [counters, recent, failures, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Percentage(50),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let total = self.valid + self.invalid + self.errors;
+        let seconds = self.started.elapsed().as_secs_f64();
+        let throughput = if seconds > 0.0 {
+            total as f64 / seconds
+        } else {
+            0.0
+        };
+        let title = if self.done { "Done" } else { "Verifying" };
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{} lines   OK {}   BAD {}   ERROR {}   {:.0} lines/s",
+                total, self.valid, self.invalid, self.errors, throughput
+            ))
+            .block(Block::default().borders(Borders::ALL).title(title)),
+            counters,
+        );
+
+        let visible = recent.height.saturating_sub(3) as usize;
+        let rows = self
+            .recent
+            .iter()
+            .rev()
+            .take(visible)
+            .map(|(address, verdict, detail)| {
+                let color = match *verdict {
+                    "OK" => Color::Green,
+                    "BAD" => Color::Red,
+                    _ => Color::Yellow,
+                };
+                Row::new([address.as_str(), verdict, detail.as_str()])
+                    .style(Style::default().fg(color))
+            });
+        frame.render_widget(
+            Table::new(
+                rows,
+                [
+                    Constraint::Fill(1),
+                    Constraint::Length(6),
+                    Constraint::Length(20),
+                ],
+            )
+            .header(Row::new(["Address", "Result", "Error"]))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Latest results"),
+            ),
+            recent,
+        );
+
+        let matching: Vec<Line> = self
+            .failures
+            .iter()
+            .filter(|f| f.contains(&self.filter))
+            .map(|f| Line::raw(f.as_str()))
+            .collect();
+        let title = match (self.editing, self.filter.is_empty()) {
+            (true, _) => format!("Failures, filter: {}_", self.filter),
+            (false, true) => format!("Failures ({})", matching.len()),
+            (false, false) => format!("Failures matching `{}` ({})", self.filter, matching.len()),
+        };
+        let skip = matching
+            .len()
+            .saturating_sub(failures.height.saturating_sub(2) as usize);
+        frame.render_widget(
+            List::new(matching.into_iter().skip(skip))
+                .block(Block::default().borders(Borders::ALL).title(title)),
+            failures,
+        );
+
+        let keys = if self.done {
+            "q quit   / filter failures   Esc clear filter"
+        } else {
+            "q stop   / filter failures   Esc clear filter"
+        };
+        frame.render_widget(Paragraph::new(keys), help);
+    }
+}
+
+/// The dashboard on the alternate screen of the terminal.
+pub struct Dashboard {
+    state: State,
+    terminal: DefaultTerminal,
+    drawn: Instant,
+}
+
+fn interrupted() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "stopped from the dashboard")
+}
+
+impl Dashboard {
+    /// Switches the terminal to the dashboard, until [`Dashboard::finish`] or a drop.
+    /// Logging is off meanwhile, it would draw over the dashboard.
+    pub fn start() -> io::Result<Self> {
+        let terminal = ratatui::try_init()?;
+        logging::mute(true);
+
+        Ok(Dashboard {
+            state: State::new(),
+            terminal,
+            drawn: Instant::now() - FRAME,
+        })
+    }
+
+    /// Handles the pending key presses and redraws, at most once a frame. Fails with
+    /// `ErrorKind::Interrupted` once the user quits.
+    fn update(&mut self) -> io::Result<()> {
+        if self.drawn.elapsed() < FRAME {
+            return Ok(());
+        }
+
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.state.key(key.code, key.modifiers) {
+                    return Err(interrupted());
+                }
+            }
+        }
+        self.terminal.draw(|frame| self.state.render(frame))?;
+        self.drawn = Instant::now();
+        Ok(())
+    }
+
+    /// Adds the result of one line.
+    pub fn push(&mut self, address: &str, result: Result<bool, &str>) -> io::Result<()> {
+        self.state.push(address, result);
+        self.update()
+    }
+
+    /// Adds a line to the failure list, e.g. the `FAILED` or `SIGNER` notes.
+    pub fn note(&mut self, line: String) {
+        self.state.failures.push(line);
+    }
+
+    /// Shows the final state until the user quits, then restores the terminal.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.state.done = true;
+        loop {
+            self.terminal.draw(|frame| self.state.render(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.state.key(key.code, key.modifiers) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        ratatui::restore();
+        logging::mute(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn check_dashboard() {
+        let mut state = State::new();
+        state.push("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m", Ok(true));
+        state.push("1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu", Ok(false));
+        state.push("1Fb", Err("address_parse"));
+        assert_eq!((state.valid, state.invalid, state.errors), (1, 1, 1));
+        assert_eq!(state.failures.len(), 2);
+
+        assert!(state.key(KeyCode::Char('/'), KeyModifiers::NONE));
+        for c in "1Bp".chars() {
+            assert!(state.key(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        // While editing `q` is part of the filter.
+        assert!(state.key(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert!(state.key(KeyCode::Backspace, KeyModifiers::NONE));
+        assert!(state.key(KeyCode::Enter, KeyModifiers::NONE));
+        assert_eq!(state.filter, "1Bp");
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| state.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("3 lines   OK 1   BAD 1   ERROR 1"));
+        assert!(screen.contains("Failures matching `1Bp` (1)"));
+        assert!(screen.contains("BAD - 1BpEi6DfDAUFd7GtittLSdBeYJvcoaVggu"));
+        assert!(!screen.contains("ERROR address_parse - 1Fb"));
+
+        assert!(!state.key(KeyCode::Char('q'), KeyModifiers::NONE));
+        assert!(!state.key(KeyCode::Char('c'), KeyModifiers::CONTROL));
+    }
+}
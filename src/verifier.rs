@@ -0,0 +1,122 @@
+// A verification context for many signatures of one message: the signed-message hash
+// is made once instead of for every signature, the secp256k1 context is the one shared
+// by the whole process, and the signatures are decoded into a stack buffer.
+
+use crate::matcher::{AddressMatcher, StandardMatcher};
+use crate::signature::{
+    decode_signature_into, parse_signature_bytes, secp, signed_msg_hash, SIGNATURE_LEN,
+};
+use crate::{bip322, schnorr, VerifyError};
+use bitcoin::address::NetworkChecked;
+use bitcoin::hashes::sha256d;
+use bitcoin::secp256k1::{self, Message};
+use bitcoin::sign_message::MessageSignatureError;
+use bitcoin::Address;
+
+/// The result of one signature, see [`crate::check_sig`].
+pub type VerificationResult = Result<bool, VerifyError>;
+
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    message: Vec<u8>,
+    msg_hash: sha256d::Hash,
+}
+
+impl Verifier {
+    /// A verifier of signatures of `message`, text or (e.g. from `--message-hex`)
+    /// any bytes.
+    pub fn new(message: impl AsRef<[u8]>) -> Self {
+        let message = message.as_ref();
+        Verifier {
+            message: message.to_vec(),
+            msg_hash: signed_msg_hash(message),
+        }
+    }
+
+    /// The message the signatures are checked against.
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    /// The signed-message hash of [`Verifier::message`].
+    pub fn msg_hash(&self) -> sha256d::Hash {
+        self.msg_hash
+    }
+
+    /// Verifies `signature` by `address` like [`crate::check_sig`] does.
+    pub fn verify_one(
+        &self,
+        address: &Address<NetworkChecked>,
+        signature: &str,
+    ) -> VerificationResult {
+        let mut buf = [0; SIGNATURE_LEN];
+        let bytes = decode_signature_into(signature, &mut buf)?;
+        if bytes.len() == SIGNATURE_LEN {
+            let signature = parse_signature_bytes(&bytes)?;
+            let pubkey = signature.recover_pubkey(secp(), self.msg_hash)?;
+            return Ok(StandardMatcher.matches(&pubkey, address));
+        }
+        if bytes.len() == 64 {
+            if let Some(output_key) = schnorr::taproot_output_key(address) {
+                let signature = secp256k1::schnorr::Signature::from_slice(&bytes)
+                    .map_err(MessageSignatureError::InvalidEncoding)?;
+                let msg = Message::from_slice(&self.msg_hash[..])
+                    .map_err(MessageSignatureError::InvalidEncoding)?;
+                return Ok(secp().verify_schnorr(&signature, &msg, &output_key).is_ok());
+            }
+        }
+
+        let proof = bip322::decode_proof(&bytes)?;
+        bip322::verify(address, &self.message, &proof)
+    }
+
+    /// Verifies `(address, signature)` pairs, the results in the same order.
+    pub fn verify_batch(
+        &self,
+        signatures: &[(Address<NetworkChecked>, &str)],
+    ) -> Vec<VerificationResult> {
+        signatures
+            .iter()
+            .map(|(address, signature)| self.verify_one(address, signature))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MESSAGE;
+    use bitcoin::Network;
+
+    #[test]
+    fn check_verifier_batch() {
+        let address = |a: &str| {
+            a.parse::<Address<_>>()
+                .unwrap()
+                .require_network(Network::Bitcoin)
+                .unwrap()
+        };
+        let verifier = Verifier::new(MESSAGE);
+        assert_eq!(verifier.message(), MESSAGE.as_bytes());
+        assert_eq!(verifier.msg_hash(), signed_msg_hash(MESSAGE));
+
+        let results = verifier.verify_batch(&[
+            (
+                address("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"),
+                "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=",
+            ),
+            (
+                address("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"),
+                "HFjd/SzCNDyXRY/skSjEKusK/adVtBf0ldT1ayvPb+WsLa5Qr0A4seEXjOmtg9K/wcJnv/E3F5TezZNB/ULoZI8=",
+            ),
+            (address("1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m"), "not base64!"),
+        ]);
+        assert_eq!(results.len(), 3);
+        assert!(matches!(results[0], Ok(true)));
+        assert!(matches!(results[1], Ok(false)));
+        assert!(matches!(
+            results[2],
+            Err(VerifyError::SignatureBase64DecodeError(_))
+        ));
+    }
+}
@@ -0,0 +1,77 @@
+// JavaScript bindings for running the check in a browser, built with
+//
+//     wasm-pack build --target web -- --features wasm
+//
+// and used as
+//
+//     import init, { verify } from "./pkg/craigwrightisnotsatoshi_check.js";
+//     await init();
+//     const result = verify(address, signature);  // or verify(address, signature, message)
+//     result.valid, result.error
+
+use crate::{verify_message_on, MESSAGE};
+use bitcoin::Network;
+use wasm_bindgen::prelude::*;
+
+/// Outcome of one check; `error` is one of the codes of the JSON output.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    valid: bool,
+    error: Option<String>,
+}
+
+#[wasm_bindgen]
+impl Verification {
+    /// Whether the signature is a valid one of the message by the address.
+    #[wasm_bindgen(getter)]
+    pub fn valid(&self) -> bool {
+        self.valid
+    }
+
+    /// Why the signature couldn't be checked at all, `undefined` if it could.
+    #[wasm_bindgen(getter)]
+    pub fn error(&self) -> Option<String> {
+        self.error.clone()
+    }
+}
+
+/// Checks a signature of `message` (the built-in one if omitted) by a mainnet address.
+#[wasm_bindgen]
+pub fn verify(address: &str, signature: &str, message: Option<String>) -> Verification {
+    let message = message.as_deref().unwrap_or(MESSAGE);
+
+    match verify_message_on(Network::Bitcoin, address, message, signature) {
+        Ok(valid) => Verification { valid, error: None },
+        Err(e) => Verification {
+            valid: false,
+            error: Some(e.code().to_string()),
+        },
+    }
+}
+
+/// The statement signed by the addresses on the list.
+#[wasm_bindgen(js_name = defaultMessage)]
+pub fn default_message() -> String {
+    MESSAGE.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_wasm_verify() {
+        let address = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m";
+        let signature = "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+
+        assert!(verify(address, signature, None).valid());
+        let result = verify(address, signature, Some("other".to_string()));
+        assert!(!result.valid());
+        assert_eq!(result.error(), None);
+        assert_eq!(
+            verify("1Fb", signature, None).error().as_deref(),
+            Some("address_parse")
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use crate::input::Record;
+use bitcoin::hashes::{sha256, Hash};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+/// Where the list of signatures is published.
+pub const WEBSITE_URL: &str = "https://craigwrightisnotsatoshi.com";
+
+/// The copy of the published page kept in this repository.
+pub const GITHUB_LIST_PATH: &str = "backup/craigwrightisnotsatoshi-com.html";
+
+/// A GitHub repository pinned to a commit, `<owner>/<repo>@<commit>`. Only full
+/// commit hashes are taken, branches and tags can be moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GithubPin {
+    pub owner: String,
+    pub repo: String,
+    pub commit: String,
+}
+
+impl FromStr for GithubPin {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (repository, commit) = s
+            .split_once('@')
+            .ok_or("expected `<owner>/<repo>@<commit>`")?;
+        let (owner, repo) = repository
+            .split_once('/')
+            .filter(|(owner, repo)| !owner.is_empty() && !repo.is_empty() && !repo.contains('/'))
+            .ok_or("expected `<owner>/<repo>@<commit>`")?;
+        if commit.len() != 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("`{}` isn't a full 40 digit commit hash", commit));
+        }
+
+        Ok(GithubPin {
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            commit: commit.to_ascii_lowercase(),
+        })
+    }
+}
+
+impl fmt::Display for GithubPin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}@{}", self.owner, self.repo, self.commit)
+    }
+}
+
+impl GithubPin {
+    /// Where the contents of `path` at the commit are served.
+    pub fn raw_url(&self, path: &str) -> String {
+        format!(
+            "https://raw.githubusercontent.com/{}/{}/{}/{}",
+            self.owner,
+            self.repo,
+            self.commit,
+            path.trim_start_matches('/')
+        )
+    }
+}
+
+fn is_legacy_address(s: &str) -> bool {
+    (s.starts_with('1') || s.starts_with('3'))
+        && (26..=35).contains(&s.len())
+        && s.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+fn is_base64(s: &str) -> bool {
+    s.chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=' | '_'))
+}
+
+/// The character a reference like `&amp;`, `&#43;` or `&#x2F;` stands for.
+fn character_reference(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => match name.strip_prefix('#')? {
+            hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok(),
+            decimal => decimal.parse().ok(),
+        }
+        .and_then(char::from_u32),
+    }
+}
+
+/// The text of some markup: the tags dropped, those ending rows, cells or lines
+/// turned into whitespace, and the character references decoded.
+fn markup_text(markup: &str) -> String {
+    let mut text = String::with_capacity(markup.len());
+    let mut rest = markup;
+
+    while let Some(i) = rest.find(['<', '&']) {
+        text.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with('<') {
+            let Some(end) = rest.find('>') else { break };
+            let name = rest[1..end]
+                .trim_start_matches('/')
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or_default()
+                .to_ascii_lowercase();
+            match name.as_str() {
+                "br" | "tr" | "p" | "div" | "li" | "pre" => text.push('\n'),
+                "td" | "th" => text.push(' '),
+                _ => {}
+            }
+            rest = &rest[end + 1..];
+        } else {
+            match rest[1..].find(';').filter(|&end| end <= 8) {
+                Some(end) if character_reference(&rest[1..end + 1]).is_some() => {
+                    text.extend(character_reference(&rest[1..end + 1]));
+                    rest = &rest[end + 2..];
+                }
+                _ => {
+                    text.push('&');
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+    text.push_str(rest);
+
+    text
+}
+
+/// The `<address> <signature>` rows of a page: those of the first `<pre>` block
+/// (the same ones `down.py` extracts) or, on a page without one, of the whole
+/// markup, e.g. a table with a row per address.
+pub fn parse_page(html: &str) -> Vec<Record> {
+    let region = match html.find("<pre>") {
+        Some(start) => {
+            let pre = &html[start + "<pre>".len()..];
+            match pre.find("</pre>") {
+                Some(end) => &pre[..end],
+                None => pre,
+            }
+        }
+        None => html,
+    };
+
+    markup_text(region)
+        .lines()
+        .filter_map(|line| {
+            let chunks: Vec<&str> = line.split_whitespace().collect();
+            match chunks[..] {
+                [address, signature] if is_legacy_address(address) && is_base64(signature) => {
+                    Some(Record {
+                        address: address.to_string(),
+                        signature: signature.to_string(),
+                        message: None,
+                        extra: Vec::new(),
+                    })
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Downloads the page at `url` (an HTTPS one normally).
+#[cfg(feature = "fetch")]
+pub fn fetch_page(url: &str) -> io::Result<String> {
+    ureq::get(url)
+        .call()
+        .map_err(io::Error::other)?
+        .into_string()
+}
+
+/// The SHA-256 of the downloaded `contents` of `url`, an error if it isn't the
+/// `expected` one. Without an expected digest nothing is checked.
+pub fn check_digest(
+    url: &str,
+    contents: &str,
+    expected: Option<sha256::Hash>,
+) -> io::Result<sha256::Hash> {
+    let digest = sha256::Hash::hash(contents.as_bytes());
+    match expected {
+        Some(expected) if expected != digest => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} has the SHA-256 {}, expected {}", url, digest, expected),
+        )),
+        _ => Ok(digest),
+    }
+}
+
+/// Downloads `path` of the pinned repository, returning the contents with their
+/// digest checked by [`check_digest`].
+#[cfg(feature = "fetch")]
+pub fn fetch_pinned(
+    pin: &GithubPin,
+    path: &str,
+    expected: Option<sha256::Hash>,
+) -> io::Result<(String, sha256::Hash)> {
+    let url = pin.raw_url(path);
+    let text = fetch_page(&url)?;
+    let digest = check_digest(&url, &text, expected)?;
+
+    Ok((text, digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_parse_page() {
+        let page = include_str!("../backup/craigwrightisnotsatoshi-com.html");
+
+        let records = parse_page(page);
+        assert_eq!(records.len(), 145);
+        assert_eq!(records[0].address, "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m");
+        assert_eq!(
+            records[0].signature,
+            "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI="
+        );
+
+        assert!(parse_page("<html>no list</html>").is_empty());
+
+        // A table, with the characters of the signature escaped and a break opportunity.
+        let table = r#"<table>
+<tr><th>Address</th><th>Signature</th></tr>
+<tr><td><a href="/a/1Fb">1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m</a></td><td>G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz&#43;3mHsuEJn58lZlRksvazOKAtuMUMolg<wbr>&#x2F;hE9WI=</td></tr>
+<tr><td>12cFuwo1i3FMhkmJoCN8D4SXaN2DQ6iBS</td><td>HFjd&amp;</td></tr>
+</table>"#;
+        let records = parse_page(table);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].signature, parse_page(page)[0].signature);
+    }
+
+    #[test]
+    fn check_github_pin() {
+        let pin: GithubPin =
+            "seberm/craigwrightisnotsatoshi-check@1EB7644C0FFEE0000000000000000000000000AB"
+                .parse()
+                .unwrap();
+        assert_eq!(pin.commit, "1eb7644c0ffee0000000000000000000000000ab");
+        assert_eq!(
+            pin.raw_url(GITHUB_LIST_PATH),
+            "https://raw.githubusercontent.com/seberm/craigwrightisnotsatoshi-check/\
+             1eb7644c0ffee0000000000000000000000000ab/backup/craigwrightisnotsatoshi-com.html"
+        );
+        assert_eq!(pin.to_string().parse(), Ok(pin));
+
+        let digest = sha256::Hash::hash(b"abc");
+        assert_eq!(check_digest("list", "abc", Some(digest)).unwrap(), digest);
+        assert_eq!(check_digest("list", "abc", None).unwrap(), digest);
+        assert!(check_digest("list", "abd", Some(digest)).is_err());
+
+        assert!("seberm/repo@main".parse::<GithubPin>().is_err());
+        assert!("seberm@1eb7644c0ffee0000000000000000000000000ab"
+            .parse::<GithubPin>()
+            .is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::matcher::standard_addresses;
+use crate::signature::secp;
+use bitcoin::bip32::{ChildNumber, DerivationPath, ExtendedPubKey};
+use bitcoin::secp256k1;
+use bitcoin::{Address, Network, PublicKey};
+use std::collections::{HashMap, HashSet};
+
+/// Derives the first `range` non-hardened children (`xpub/0` .. `xpub/range-1`).
+pub fn derive_xpub_keys(
+    xpub: &ExtendedPubKey,
+    range: u32,
+) -> Result<Vec<secp256k1::PublicKey>, bitcoin::bip32::Error> {
+    let secp = secp();
+
+    (0..range)
+        .map(|i| {
+            let child = xpub.ckd_pub(secp, ChildNumber::from_normal_idx(i)?)?;
+            Ok(child.public_key)
+        })
+        .collect()
+}
+
+/// Finds which of `addresses` are standard addresses of the children `path/i` of
+/// `xpub`, the way a wallet scans: derivation stops once `gap_limit` consecutive
+/// children have none of them. Returns the child index of every address found.
+pub fn scan_wallet(
+    xpub: &ExtendedPubKey,
+    path: &DerivationPath,
+    addresses: &HashSet<Address>,
+    gap_limit: u32,
+    network: Network,
+) -> Result<HashMap<Address, u32>, bitcoin::bip32::Error> {
+    let secp = secp();
+    let chain = xpub.derive_pub(secp, path)?;
+
+    let mut found = HashMap::new();
+    let mut end = gap_limit;
+    let mut i = 0;
+    while i < end {
+        let child = chain.ckd_pub(secp, ChildNumber::from_normal_idx(i)?)?;
+        for address in standard_addresses(&PublicKey::new(child.public_key), network) {
+            if addresses.contains(&address) {
+                found.insert(address, i);
+                end = i.saturating_add(1).saturating_add(gap_limit);
+            }
+        }
+        i += 1;
+    }
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::recover_pubkey;
+    use crate::MESSAGE;
+    use bitcoin::secp256k1::Secp256k1;
+    use bitcoin::sign_message::{signed_msg_hash, MessageSignature};
+    use bitcoin::Network;
+
+    #[test]
+    fn check_xpub_derivation_match() {
+        use bitcoin::bip32::ExtendedPrivKey;
+        use bitcoin::secp256k1::Message;
+
+        let secp = Secp256k1::new();
+        let xpriv = ExtendedPrivKey::new_master(Network::Bitcoin, &[7u8; 32]).unwrap();
+        let xpub = ExtendedPubKey::from_priv(&secp, &xpriv);
+
+        let child = xpriv
+            .ckd_priv(&secp, ChildNumber::from_normal_idx(3).unwrap())
+            .unwrap();
+        let msg = Message::from(signed_msg_hash(MESSAGE));
+        let sig =
+            MessageSignature::new(secp.sign_ecdsa_recoverable(&msg, &child.private_key), true);
+
+        let keys = derive_xpub_keys(&xpub, 5).unwrap();
+        let pubkey = recover_pubkey(MESSAGE, &sig.to_base64()).unwrap();
+        assert_eq!(keys.iter().position(|k| *k == pubkey.inner), Some(3));
+
+        let keys = derive_xpub_keys(&xpub, 3).unwrap();
+        assert_eq!(keys.iter().position(|k| *k == pubkey.inner), None);
+    }
+
+    #[test]
+    fn check_scan_wallet() {
+        let secp = Secp256k1::new();
+        let xpriv =
+            bitcoin::bip32::ExtendedPrivKey::new_master(Network::Bitcoin, &[7u8; 32]).unwrap();
+        let xpub = ExtendedPubKey::from_priv(&secp, &xpriv);
+        let path: DerivationPath = "m/0".parse().unwrap();
+        let child = |i: u32| {
+            let key = xpub
+                .derive_pub(&secp, &path.child(ChildNumber::from_normal_idx(i).unwrap()))
+                .unwrap();
+            PublicKey::new(key.public_key)
+        };
+
+        let near = Address::p2wpkh(&child(4), Network::Bitcoin).unwrap();
+        let chained = Address::p2pkh(&child(8), Network::Bitcoin);
+        let far = Address::p2pkh(&child(30), Network::Bitcoin);
+        let addresses = HashSet::from([near.clone(), chained.clone(), far.clone()]);
+
+        let found = scan_wallet(&xpub, &path, &addresses, 5, Network::Bitcoin).unwrap();
+        assert_eq!(found.get(&near), Some(&4));
+        assert_eq!(found.get(&chained), Some(&8));
+        assert_eq!(found.get(&far), None);
+
+        let found = scan_wallet(&xpub, &path, &addresses, 22, Network::Bitcoin).unwrap();
+        assert_eq!(found.get(&far), Some(&30));
+    }
+}
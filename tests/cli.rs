@@ -0,0 +1,278 @@
+// Runs of the binary for what only shows in its output and exit status: the
+// duplicate address handling, the summary line, the output order of --jobs, the
+// results taken over by --resume, stopping early and a reader that goes away.
+
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+const ADDRESS: &str = "1FbPLPR1XoufBQRPGd9JBLPbKLaGjbax5m";
+const OK: &str =
+    "G3SsgKMKAOiOaMzKSGqpKo5MFpt0biP9MbO5UkSl7VxRKcv6Uz+3mHsuEJn58lZlRksvazOKAtuMUMolg/hE9WI=";
+/// A signature by another address.
+const BAD: &str =
+    "HFjd/SzCNDyXRY/skSjEKusK/adVtBf0ldT1ayvPb+WsLa5Qr0A4seEXjOmtg9K/wcJnv/E3F5TezZNB/ULoZI8=";
+
+/// Writes `content` to a file of the test's `name`.
+fn input(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("cwins-check-cli-{}.txt", name));
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+fn check(args: &[&str], input: &PathBuf) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_craigwrightisnotsatoshi-check"))
+        .args(args)
+        .arg(input)
+        .output()
+        .unwrap()
+}
+
+/// Runs the binary with a stdout nobody reads, like `| head` after it exited.
+fn closed_stdout(args: &[&str]) -> Output {
+    let (reader, writer) = std::io::pipe().unwrap();
+    drop(reader);
+    Command::new(env!("CARGO_BIN_EXE_craigwrightisnotsatoshi-check"))
+        .args(args)
+        .stdout(writer)
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn check_dedup() {
+    let path = input(
+        "dedup",
+        &format!(
+            "{} {}\n{} {}\n{} {}\n",
+            ADDRESS, OK, ADDRESS, OK, ADDRESS, OK
+        ),
+    );
+    let duplicate = format!("DUPLICATE {} - 3 times: OK\n", ADDRESS);
+
+    let keep_all = check(&["--dedup", "keep-all", "--fail-on-error"], &path);
+    assert_eq!(keep_all.status.code(), Some(0));
+    assert_eq!(stdout(&keep_all), format!("OK - {}\n", ADDRESS).repeat(3));
+    assert!(stderr(&keep_all).starts_with(&duplicate));
+    assert!(stderr(&keep_all).contains("3 lines, 3 OK, 0 BAD, 0 ERROR, 0 skipped"));
+    assert!(stderr(&keep_all).contains(", 1 duplicate addresses (0 conflicting)"));
+
+    let keep_first = check(&["--dedup", "keep-first", "--fail-on-error"], &path);
+    assert_eq!(keep_first.status.code(), Some(0));
+    assert_eq!(stdout(&keep_first), format!("OK - {}\n", ADDRESS));
+    assert!(stderr(&keep_first).starts_with(&duplicate));
+    assert!(stderr(&keep_first)
+        .contains("3 lines, 1 OK, 0 BAD, 0 ERROR, 0 skipped, 2 duplicates left out"));
+    let keep_first = check(&["--dedup", "keep-first", "--max-errors", "1"], &path);
+    assert_eq!(keep_first.status.code(), Some(0));
+    assert!(!stderr(&keep_first).contains("ABORTED"));
+
+    let error = check(&["--dedup", "error"], &path);
+    assert_eq!(error.status.code(), Some(0));
+    assert!(stderr(&error).contains("3 lines, 1 OK, 0 BAD, 2 ERROR, 0 skipped"));
+    let error = check(&["--dedup", "error", "--fail-on-error"], &path);
+    assert_eq!(error.status.code(), Some(2));
+    let json = check(&["--dedup", "error", "--output-format", "ndjson"], &path);
+    assert_eq!(stdout(&json).matches("\"error\":\"duplicate\"").count(), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn check_unordered() {
+    let lines: Vec<String> = (0..40)
+        .map(|i| format!("{} {}\n", ADDRESS, if i % 3 == 0 { BAD } else { OK }))
+        .collect();
+    let path = input("unordered", &lines.concat());
+    let results =
+        |output: &Output| -> Vec<String> { stdout(output).lines().map(str::to_string).collect() };
+
+    let ordered = results(&check(&["--jobs", "4"], &path));
+    let expected: Vec<_> = (0..40)
+        .map(|i| format!("{} - {}", if i % 3 == 0 { "BAD" } else { "OK" }, ADDRESS))
+        .collect();
+    assert_eq!(ordered, expected);
+
+    // The same results, in whatever order the threads got done with them.
+    let mut unordered = results(&check(&["--jobs", "4", "--unordered"], &path));
+    unordered.sort();
+    let mut expected = expected;
+    expected.sort();
+    assert_eq!(unordered, expected);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn check_resume() {
+    let path = input(
+        "resume",
+        &format!("{} {}\n{} {}\n", ADDRESS, BAD, ADDRESS, OK),
+    );
+    // The earlier run says valid for the BAD signature, which only a result taken
+    // over instead of verified again keeps.
+    let report = stdout(&check(&["--output-format", "ndjson"], &path));
+    assert!(report.contains("\"message_sha256\":") && report.contains("\"options_sha256\":"));
+    let prior = input(
+        "resume-prior",
+        &report.replacen("\"result\":\"invalid\"", "\"result\":\"valid\"", 1),
+    );
+    let resume = |args: &[&str]| {
+        let mut all = vec![
+            "--resume",
+            prior.to_str().unwrap(),
+            "--output-format",
+            "ndjson",
+        ];
+        all.extend(args);
+        check(&all, &path)
+    };
+
+    let output = resume(&[]);
+    let results = stdout(&output);
+    let rows: Vec<&str> = results.lines().collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0].contains("\"result\":\"valid\"") && rows[0].contains(BAD));
+    assert!(rows[1].contains("\"result\":\"valid\"") && rows[1].contains(OK));
+    assert!(stderr(&output).contains(&format!(
+        "RESUMED - 2 results taken over from {}, 0 newly verified\n",
+        prior.display()
+    )));
+
+    // Results of another message are verified again.
+    let output = resume(&["-m", "something else"]);
+    assert_eq!(stdout(&output).matches("\"result\":\"invalid\"").count(), 2);
+    assert!(stderr(&output).contains(&format!(
+        "RESUMED - 0 results taken over from {}, 2 newly verified \
+         (2 there of another message or other options)",
+        prior.display()
+    )));
+
+    // And so are those of other options, which apply to the lines either way.
+    let output = resume(&["--allow-address-types", "p2wpkh"]);
+    assert_eq!(stdout(&output).matches("\"error\":\"rejected\"").count(), 2);
+
+    std::fs::remove_file(&prior).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn check_csv_schema_version() {
+    let path = input("csv", &format!("{} {}\n", ADDRESS, OK));
+    let output = stdout(&check(&["--output-format", "csv"], &path));
+    let mut lines = output.lines();
+    assert_eq!(
+        lines.next(),
+        Some("address,signature,result,error,pubkey,schema_version")
+    );
+    assert!(lines.next().unwrap().ends_with(",1"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn check_conflicting_duplicates() {
+    let path = input(
+        "conflicting",
+        &format!("{} {}\n{} {}\n", ADDRESS, OK, ADDRESS, BAD),
+    );
+
+    let output = check(&[], &path);
+    assert_eq!(
+        stdout(&output),
+        format!("OK - {}\nBAD - {}\n", ADDRESS, ADDRESS)
+    );
+    assert!(stderr(&output).starts_with(&format!("DUPLICATE {} - 2 times: BAD, OK\n", ADDRESS)));
+    assert!(stderr(&output).contains(", 1 duplicate addresses (1 conflicting)"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn check_aborted_output() {
+    let path = input(
+        "aborted",
+        &format!("{} {}\n{} {}\n", ADDRESS, BAD, ADDRESS, OK),
+    );
+    let output = std::env::temp_dir().join("cwins-check-cli-aborted.json");
+    std::fs::write(&output, "old report\n").unwrap();
+
+    let args = ["--output-format", "json", "--max-errors", "1", "--output"];
+    let aborted = Command::new(env!("CARGO_BIN_EXE_craigwrightisnotsatoshi-check"))
+        .args(args)
+        .arg(&output)
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert_eq!(aborted.status.code(), Some(1));
+    assert!(stderr(&aborted).contains("ABORTED - 1 BAD"));
+    assert!(stderr(&aborted).contains("isn't written, the run stopped before the end"));
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "old report\n");
+
+    std::fs::remove_file(&output).unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn check_interrupt() {
+    let path = input("interrupt", &format!("{} {}\n", ADDRESS, OK));
+    let mut child = Command::new(env!("CARGO_BIN_EXE_craigwrightisnotsatoshi-check"))
+        .arg("--watch")
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The first line is verified, the run then waits for more.
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, format!("OK - {}\n", ADDRESS));
+
+    let killed = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+    let status = child.wait().unwrap();
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .unwrap();
+
+    assert_eq!(status.code(), Some(130));
+    assert!(stderr.contains("INTERRUPTED - the rest of the input isn't verified"));
+    assert!(stderr.contains("1 lines, 1 OK, 0 BAD, 0 ERROR, 0 skipped"));
+    assert!(stderr.contains(", run interrupted"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn check_broken_pipe() {
+    let output = closed_stdout(&["--dump-message-hash"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(stderr(&output), "");
+
+    let path = input("broken-pipe", &format!("{} {}\n", ADDRESS, OK).repeat(10));
+    for format in ["text", "ndjson", "csv"] {
+        let output = closed_stdout(&["--output-format", format, path.to_str().unwrap()]);
+        assert_eq!(output.status.code(), Some(0), "{}", format);
+        assert_eq!(stderr(&output), "", "{}", format);
+    }
+    std::fs::remove_file(&path).unwrap();
+}